@@ -0,0 +1,310 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use image::{imageops::FilterType, ImageFormat, ImageReader};
+use plist::{Dictionary, Value};
+use serde::Deserialize;
+
+const PRIMARY_ICON_SET: &str = "AppIcon";
+
+// Sizes generated from a single 1024x1024 "universal" icon, as (idiom, size, scale)
+const UNIVERSAL_ICON_SIZES: &[(&str, &str, u32)] = &[
+    ("iphone", "20x20", 2),
+    ("iphone", "20x20", 3),
+    ("iphone", "29x29", 2),
+    ("iphone", "29x29", 3),
+    ("iphone", "40x40", 2),
+    ("iphone", "40x40", 3),
+    ("iphone", "60x60", 2),
+    ("iphone", "60x60", 3),
+    ("ipad", "20x20", 1),
+    ("ipad", "20x20", 2),
+    ("ipad", "29x29", 1),
+    ("ipad", "29x29", 2),
+    ("ipad", "40x40", 1),
+    ("ipad", "40x40", 2),
+    ("ipad", "76x76", 1),
+    ("ipad", "76x76", 2),
+    ("ipad", "83.5x83.5", 2),
+];
+
+#[derive(Deserialize)]
+struct Contents {
+    #[serde(default)]
+    images: Vec<ContentsImage>,
+}
+
+#[derive(Deserialize)]
+struct ContentsImage {
+    filename: Option<String>,
+    idiom: Option<String>,
+    scale: Option<String>,
+    size: Option<String>,
+    // Dark and tinted variants have no loose-file equivalent
+    #[serde(default)]
+    appearances: Vec<serde_json::Value>,
+}
+
+#[derive(Default)]
+pub struct CompiledAssets {
+    pub info_plist: Vec<(String, Value)>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Default)]
+struct IconFiles {
+    iphone: Vec<String>,
+    ipad: Vec<String>,
+}
+
+impl IconFiles {
+    // iPads fall back to the iPhone icons for sizes they don't have their own of
+    fn for_idiom(&self, ipad: bool) -> Vec<String> {
+        let mut files = self.iphone.clone();
+        if ipad {
+            for file in &self.ipad {
+                if !files.contains(file) {
+                    files.push(file.clone());
+                }
+            }
+        }
+        files
+    }
+}
+
+// There is no actool off macOS, so catalogs are flattened into loose files that
+// UIImage(named:) and the icon keys in Info.plist can find. Nothing here produces an
+// Assets.car, so anything that only exists in a compiled catalog is reported instead.
+pub fn compile_asset_catalogs(
+    project_path: &Path,
+    app_path: &Path,
+) -> Result<CompiledAssets, String> {
+    let mut compiled = CompiledAssets::default();
+    let mut icon_sets: Vec<(String, IconFiles)> = Vec::new();
+
+    for catalog in find_catalogs(project_path) {
+        let catalog_name = catalog
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let walker = walkdir::WalkDir::new(&catalog)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_dir());
+        for entry in walker {
+            let path = entry.path();
+            let name = match path.file_stem() {
+                Some(name) => name.to_string_lossy().to_string(),
+                None => continue,
+            };
+            match path.extension().and_then(|e| e.to_str()) {
+                Some("appiconset") => {
+                    let files = compile_icon_set(path, &name, app_path, &mut compiled.warnings)?;
+                    icon_sets.push((name, files));
+                }
+                Some("imageset") => {
+                    compile_image_set(path, &name, app_path, &mut compiled.warnings)?
+                }
+                Some("colorset") => compiled.warnings.push(format!(
+                    "{}: color set \"{}\" needs a compiled Assets.car and will not be available at runtime",
+                    catalog_name, name
+                )),
+                Some(ext) if ext.ends_with("set") => compiled.warnings.push(format!(
+                    "{}: {} \"{}\" is not supported and was skipped",
+                    catalog_name, ext, name
+                )),
+                _ => {}
+            }
+        }
+    }
+
+    if icon_sets.is_empty() {
+        return Ok(compiled);
+    }
+    let primary = icon_sets
+        .iter()
+        .position(|(name, _)| name == PRIMARY_ICON_SET)
+        .unwrap_or(0);
+    let (_, primary_files) = icon_sets.remove(primary);
+
+    compiled.info_plist.push((
+        "CFBundleIcons".to_string(),
+        icons_dict(&primary_files, &icon_sets, false),
+    ));
+    compiled.info_plist.push((
+        "CFBundleIcons~ipad".to_string(),
+        icons_dict(&primary_files, &icon_sets, true),
+    ));
+
+    Ok(compiled)
+}
+
+fn find_catalogs(project_path: &Path) -> Vec<PathBuf> {
+    walkdir::WalkDir::new(project_path)
+        .into_iter()
+        .filter_entry(|e| {
+            let name = e.file_name().to_string_lossy();
+            e.depth() == 0 || !name.starts_with('.')
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_dir() && e.path().extension().is_some_and(|x| x == "xcassets"))
+        .map(|e| e.into_path())
+        .collect()
+}
+
+fn read_contents(set: &Path) -> Result<Contents, String> {
+    let path = set.join("Contents.json");
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn compile_icon_set(
+    set: &Path,
+    name: &str,
+    app_path: &Path,
+    warnings: &mut Vec<String>,
+) -> Result<IconFiles, String> {
+    let mut files = IconFiles::default();
+    for image in read_contents(set)?.images {
+        let filename = match &image.filename {
+            Some(filename) if image.appearances.is_empty() => filename,
+            _ => continue,
+        };
+        let source = set.join(filename);
+        match (image.idiom.as_deref(), &image.size, &image.scale) {
+            (Some(idiom @ ("iphone" | "ipad")), Some(size), Some(scale)) => {
+                let base = format!("{}{}", name, size);
+                let dest = app_path.join(icon_file_name(&base, scale, idiom));
+                fs::copy(&source, &dest)
+                    .map_err(|e| format!("Failed to copy {}: {}", source.display(), e))?;
+                add_icon_file(&mut files, idiom, base);
+            }
+            // Xcode 14 single-size icons, the smaller ones are rendered here
+            (Some("universal"), Some(_), None) => {
+                let img = ImageReader::open(&source)
+                    .map_err(|e| format!("Failed to open {}: {}", source.display(), e))?
+                    .decode()
+                    .map_err(|e| format!("Failed to decode {}: {}", source.display(), e))?;
+                for (idiom, size, scale) in UNIVERSAL_ICON_SIZES {
+                    let points: f32 = size.split('x').next().unwrap().parse().unwrap();
+                    let pixels = (points * *scale as f32) as u32;
+                    let base = format!("{}{}", name, size);
+                    let dest = app_path.join(icon_file_name(&base, &format!("{}x", scale), idiom));
+                    img.resize_exact(pixels, pixels, FilterType::CatmullRom)
+                        .save_with_format(&dest, ImageFormat::Png)
+                        .map_err(|e| format!("Failed to save {}: {}", dest.display(), e))?;
+                    add_icon_file(&mut files, idiom, base);
+                }
+            }
+            (Some("ios-marketing"), _, _) => {}
+            (idiom, _, _) => warnings.push(format!(
+                "App icon {} in {} (idiom {}) is not used on iOS and was skipped",
+                filename,
+                name,
+                idiom.unwrap_or("none")
+            )),
+        }
+    }
+    if files.iphone.is_empty() && files.ipad.is_empty() {
+        warnings.push(format!("App icon set \"{}\" has no usable images", name));
+    }
+    Ok(files)
+}
+
+fn compile_image_set(
+    set: &Path,
+    name: &str,
+    app_path: &Path,
+    warnings: &mut Vec<String>,
+) -> Result<(), String> {
+    for image in read_contents(set)?.images {
+        let filename = match &image.filename {
+            Some(filename) if image.appearances.is_empty() => filename,
+            Some(filename) => {
+                warnings.push(format!(
+                    "Image set \"{}\": appearance variant {} was skipped",
+                    name, filename
+                ));
+                continue;
+            }
+            None => continue,
+        };
+        let source = set.join(filename);
+        let ext = source
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if !matches!(ext.as_str(), "png" | "jpg" | "jpeg") {
+            warnings.push(format!(
+                "Image set \"{}\": {} can only be used from a compiled catalog and was skipped",
+                name, filename
+            ));
+            continue;
+        }
+        let scale = image.scale.as_deref().unwrap_or("1x");
+        let idiom = image.idiom.as_deref().unwrap_or("universal");
+        let dest = app_path.join(format!("{}.{}", image_file_base(name, scale, idiom), ext));
+        fs::copy(&source, &dest)
+            .map_err(|e| format!("Failed to copy {}: {}", source.display(), e))?;
+    }
+    Ok(())
+}
+
+fn image_file_base(name: &str, scale: &str, idiom: &str) -> String {
+    let mut base = name.to_string();
+    if scale != "1x" {
+        base.push_str(&format!("@{}", scale));
+    }
+    if idiom == "ipad" || idiom == "iphone" {
+        base.push_str(&format!("~{}", idiom));
+    }
+    base
+}
+
+fn icon_file_name(base: &str, scale: &str, idiom: &str) -> String {
+    // Icons are looked up by base name, iPhone files go without the idiom suffix
+    let idiom = if idiom == "ipad" { "ipad" } else { "universal" };
+    format!("{}.png", image_file_base(base, scale, idiom))
+}
+
+fn add_icon_file(files: &mut IconFiles, idiom: &str, base: String) {
+    let list = if idiom == "ipad" {
+        &mut files.ipad
+    } else {
+        &mut files.iphone
+    };
+    if !list.contains(&base) {
+        list.push(base);
+    }
+}
+
+fn icons_dict(primary: &IconFiles, alternates: &[(String, IconFiles)], ipad: bool) -> Value {
+    let mut icons = Dictionary::new();
+    icons.insert(
+        "CFBundlePrimaryIcon".to_string(),
+        icon_entry(&primary.for_idiom(ipad)),
+    );
+    if !alternates.is_empty() {
+        let alternates: Dictionary = alternates
+            .iter()
+            .map(|(name, set)| (name.clone(), icon_entry(&set.for_idiom(ipad))))
+            .collect();
+        icons.insert(
+            "CFBundleAlternateIcons".to_string(),
+            Value::Dictionary(alternates),
+        );
+    }
+    Value::Dictionary(icons)
+}
+
+fn icon_entry(files: &[String]) -> Value {
+    let mut entry = Dictionary::new();
+    entry.insert(
+        "CFBundleIconFiles".to_string(),
+        Value::Array(files.iter().map(|f| Value::String(f.clone())).collect()),
+    );
+    Value::Dictionary(entry)
+}
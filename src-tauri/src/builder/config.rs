@@ -1,28 +1,261 @@
-use std::path::PathBuf;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
+use plist::{Dictionary, Value};
 use serde::{Deserialize, Serialize};
+use toml_edit::{DocumentMut, Item, TableLike};
 
-use crate::builder::swift::SwiftBin;
+use crate::builder::{
+    entitlements::{load_entitlements, EntitlementsConfig},
+    migrate::read_format_version,
+    package::{SwiftPackageDump, SwiftPackageTarget, TargetType},
+    swift::SwiftBin,
+};
 
 pub const FORMAT_VERSION: u32 = 1;
+pub const DEFAULT_DEPLOYMENT_TARGET: &str = "15.0";
+pub const DEFAULT_MACOS_DEPLOYMENT_TARGET: &str = "12.0";
+pub const DEFAULT_COMPRESSION_LEVEL: u32 = 6;
 
 pub struct BuildSettings {
     pub debug: bool,
+    pub configuration: Option<String>,
+    // The product of one of the [[apps]], None for the main app
+    pub app: Option<String>,
+    pub platform: TargetPlatform,
+    pub arch: TargetArch,
+}
+
+impl BuildSettings {
+    // The name of the swift SDK triple, which is also the directory SwiftPM builds into
+    pub fn sdk_triple(&self) -> String {
+        format!(
+            "{}-apple-{}",
+            self.arch.triple_name(),
+            self.platform.triple_name()
+        )
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.platform == TargetPlatform::Ios && self.arch != TargetArch::Arm64 {
+            return Err("iOS devices can only run arm64 builds".to_string());
+        }
+        Ok(())
+    }
+}
+
+// The build options the IDE and CLI send with build_swift and deploy_swift. The platform and
+// arch default to an iOS device.
+#[derive(Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildRequest {
+    pub debug: bool,
+    #[serde(default)]
+    pub configuration: Option<String>,
+    #[serde(default)]
+    pub app: Option<String>,
+    #[serde(default)]
+    pub platform: Option<TargetPlatform>,
+    #[serde(default)]
+    pub arch: Option<TargetArch>,
+}
+
+impl From<BuildRequest> for BuildSettings {
+    fn from(request: BuildRequest) -> Self {
+        BuildSettings {
+            debug: request.debug,
+            configuration: request.configuration,
+            app: request.app,
+            platform: request.platform.unwrap_or_default(),
+            arch: request.arch.unwrap_or_default(),
+        }
+    }
+}
+
+// Every platform the generated Darwin SDK has a triple for
+#[derive(Deserialize, Serialize, Clone, Copy, Default, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum TargetPlatform {
+    #[default]
+    Ios,
+    IosSimulator,
+    Macos,
+}
+
+impl TargetPlatform {
+    fn triple_name(&self) -> &'static str {
+        match self {
+            TargetPlatform::Ios => "ios",
+            TargetPlatform::IosSimulator => "ios-simulator",
+            TargetPlatform::Macos => "macos",
+        }
+    }
+
+    // CFBundleSupportedPlatforms
+    pub fn plist_name(&self) -> &'static str {
+        match self {
+            TargetPlatform::Ios => "iPhoneOS",
+            TargetPlatform::IosSimulator => "iPhoneSimulator",
+            TargetPlatform::Macos => "MacOSX",
+        }
+    }
+
+    // Where the app and extension executables find embedded frameworks, as (app, extension)
+    pub fn framework_rpaths(&self) -> (&'static str, &'static str) {
+        match self {
+            TargetPlatform::Macos => (
+                "@executable_path/../Frameworks",
+                "@executable_path/../../../../Frameworks",
+            ),
+            _ => (
+                "@executable_path/Frameworks",
+                "@executable_path/../../Frameworks",
+            ),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Default, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetArch {
+    #[default]
+    Arm64,
+    X86_64,
+}
+
+impl TargetArch {
+    fn triple_name(&self) -> &'static str {
+        match self {
+            TargetArch::Arm64 => "arm64",
+            TargetArch::X86_64 => "x86_64",
+        }
+    }
 }
 
-// TODO: Min ios version, etc.
 pub struct ProjectConfig {
+    // The executable product, also the name of the .app
     pub product: String,
     pub version_num: String,
     pub version_string: String,
     pub bundle_id: String,
     pub project_path: PathBuf,
+    pub platform: PlatformConfig,
+    pub info_plist: toml::Table,
+    // The user's Info.plist the generated keys are merged into
+    pub info_plist_path: Option<PathBuf>,
+    pub info_plist_format: PlistFormat,
+    pub entitlements: Option<Dictionary>,
+    pub extensions: Vec<ExtensionConfig>,
+    pub launch_screen: LaunchScreenConfig,
+    pub packaging: PackagingConfig,
+    // Set when the selected configuration forces debug or release
+    pub debug: Option<bool>,
+    pub build_flags: BuildFlags,
+    // The products of every [[apps]] entry, including the one being built
+    pub apps: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct BuildFlags {
+    pub swift_conditions: Vec<String>,
+    pub swiftc_flags: Vec<String>,
+    pub linker_flags: Vec<String>,
+}
+
+pub struct ExtensionConfig {
+    pub target: String,
+    pub extension_point: String,
+    pub principal_class: Option<String>,
+    pub bundle_id: String,
+    pub info_plist: Option<PathBuf>,
+    pub entitlements: Option<Dictionary>,
+}
+
+pub struct PlatformConfig {
+    pub deployment_target: String,
+    pub macos_deployment_target: String,
+    pub device_families: Vec<DeviceFamily>,
+    pub orientations: Vec<Orientation>,
+    pub required_capabilities: Vec<String>,
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct TomlConfig {
     pub format_version: u32,
     pub project: ProjectTomlConfig,
+    #[serde(default)]
+    pub platform: PlatformTomlConfig,
+    // Extra keys merged into the generated Info.plist
+    #[serde(default, skip_serializing_if = "toml::Table::is_empty")]
+    pub info_plist: toml::Table,
+    // Either a path to a .entitlements plist or an inline table
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entitlements: Option<EntitlementsConfig>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<ExtensionTomlConfig>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub apps: Vec<AppTomlConfig>,
+    #[serde(default)]
+    pub launch_screen: LaunchScreenConfig,
+    #[serde(default, skip_serializing_if = "PackagingConfig::is_default")]
+    pub packaging: PackagingConfig,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub configurations: BTreeMap<String, ConfigurationTomlConfig>,
+}
+
+// A named flavor of the app, e.g. [configurations.staging]
+#[derive(Deserialize, Serialize, Default, Clone)]
+pub struct ConfigurationTomlConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug: Option<bool>,
+    // Appended to the bundle ID so flavors can be installed side by side
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_id_suffix: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    // Passed as -D, usable with #if in Swift
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub swift_conditions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub swiftc_flags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub linker_flags: Vec<String>,
+    #[serde(default, skip_serializing_if = "toml::Table::is_empty")]
+    pub info_plist: toml::Table,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ExtensionTomlConfig {
+    // Name of the SwiftPM executable target that builds the extension
+    pub target: String,
+    pub extension_point: String,
+    // NSExtensionPrincipalClass, the target has to be linked with -e _NSExtensionMain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub principal_class: Option<String>,
+    // Appended to the app's bundle ID, defaults to the lowercased target name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_id_suffix: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub info_plist: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entitlements: Option<EntitlementsConfig>,
+}
+
+// Another app built from the same package, e.g. a companion or a separate admin app. It shares
+// the project wide sections like [platform], but has its own identity.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct AppTomlConfig {
+    // Name of the executable product in Package.swift
+    pub product: String,
+    pub bundle_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub info_plist: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entitlements: Option<EntitlementsConfig>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -30,106 +263,441 @@ pub struct ProjectTomlConfig {
     pub version_num: String,
     pub version_string: String,
     pub bundle_id: String,
+    // The executable product of the main app, detected from Package.swift when left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product: Option<String>,
 }
 
-// TODO: Check platforms
-#[derive(Deserialize)]
-struct SwiftPackageDump {
-    name: String,
-    targets: Vec<SwiftPackageTarget>,
+#[derive(Deserialize, Serialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlistFormat {
+    #[default]
+    Xml,
+    Binary,
 }
 
-// TODO: Resources
-#[derive(Deserialize)]
-struct SwiftPackageTarget {
-    name: String,
+// Anything left empty keeps whatever the Info.plist already declares
+#[derive(Deserialize, Serialize, Default)]
+pub struct PlatformTomlConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deployment_target: Option<String>,
+    // Only used for macOS builds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub macos_deployment_target: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub device_families: Vec<DeviceFamily>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub orientations: Vec<Orientation>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_capabilities: Vec<String>,
 }
 
-#[derive(Deserialize, Serialize)]
-pub enum ProjectValidation {
-    Valid,
-    Invalid,
-    UnsupportedFormatVersion,
-    InvalidPackage,
-    InvalidToolchain,
+// Becomes the UILaunchScreen dictionary, since storyboards can't be compiled off macOS.
+// Images are looked up by name, so they have to be available at runtime. Named colors
+// only exist in a compiled Assets.car, which can't be built yet.
+#[derive(Deserialize, Serialize, Default, Clone, PartialEq)]
+pub struct LaunchScreenConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background_color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_respects_safe_area: Option<bool>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub navigation_bar: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub tab_bar: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub toolbar: bool,
+}
+
+impl LaunchScreenConfig {
+    pub fn is_configured(&self) -> bool {
+        *self != LaunchScreenConfig::default()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match &self.background_color {
+            Some(color) => Err(format!(
+                "launch_screen.background_color \"{}\" is not supported yet, color sets need a compiled Assets.car",
+                color
+            )),
+            None => Ok(()),
+        }
+    }
+
+    pub fn plist_value(&self) -> Value {
+        let mut dict = Dictionary::new();
+        if let Some(color) = &self.background_color {
+            dict.insert("UIColorName".to_string(), Value::String(color.clone()));
+        }
+        if let Some(image) = &self.image {
+            dict.insert("UIImageName".to_string(), Value::String(image.clone()));
+        }
+        if let Some(respects) = self.image_respects_safe_area {
+            dict.insert(
+                "UIImageRespectsSafeAreaInsets".to_string(),
+                Value::Boolean(respects),
+            );
+        }
+        for (enabled, key) in [
+            (self.navigation_bar, "UINavigationBar"),
+            (self.tab_bar, "UITabBar"),
+            (self.toolbar, "UIToolbar"),
+        ] {
+            if enabled {
+                dict.insert(key.to_string(), Value::Dictionary(Dictionary::new()));
+            }
+        }
+        Value::Dictionary(dict)
+    }
+}
+
+// How release builds are packaged
+#[derive(Deserialize, Serialize, Default, Clone, PartialEq)]
+pub struct PackagingConfig {
+    // 0 stores files uncompressed, 9 is the smallest and slowest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression_level: Option<u32>,
+    // Bumps version_num after every successful release build
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub increment_build_number: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub info_plist_format: Option<PlistFormat>,
+}
+
+impl PackagingConfig {
+    fn is_default(&self) -> bool {
+        *self == PackagingConfig::default()
+    }
+
+    pub fn compression_level(&self) -> u32 {
+        self.compression_level.unwrap_or(DEFAULT_COMPRESSION_LEVEL)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match self.compression_level {
+            Some(level) if level > 9 => Err(format!(
+                "Invalid compression level {}, it must be between 0 and 9",
+                level
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceFamily {
+    Iphone,
+    Ipad,
+}
+
+impl DeviceFamily {
+    pub fn plist_value(&self) -> u64 {
+        match self {
+            DeviceFamily::Iphone => 1,
+            DeviceFamily::Ipad => 2,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Orientation {
+    Portrait,
+    PortraitUpsideDown,
+    LandscapeLeft,
+    LandscapeRight,
+}
+
+impl Orientation {
+    pub fn plist_value(&self) -> &'static str {
+        match self {
+            Orientation::Portrait => "UIInterfaceOrientationPortrait",
+            Orientation::PortraitUpsideDown => "UIInterfaceOrientationPortraitUpsideDown",
+            Orientation::LandscapeLeft => "UIInterfaceOrientationLandscapeLeft",
+            Orientation::LandscapeRight => "UIInterfaceOrientationLandscapeRight",
+        }
+    }
 }
 
 impl ProjectConfig {
-    pub fn load(project_path: PathBuf, toolchain_path: &str) -> Result<Self, String> {
+    pub fn load(
+        project_path: PathBuf,
+        toolchain_path: &str,
+        configuration: Option<&str>,
+        app: Option<&str>,
+    ) -> Result<Self, String> {
         let toml_config = TomlConfig::load_or_default(project_path.clone())?;
         let swift = SwiftBin::new(toolchain_path)?;
-        let raw_package = swift
-            .command()
-            .arg("package")
-            .arg("dump-package")
-            .current_dir(&project_path)
-            .output()
-            .map_err(|e| format!("Failed to execute swift command: {}", e))?;
-        if !raw_package.status.success() {
-            return Err(format!(
-                "Failed to dump package: {}",
-                String::from_utf8_lossy(&raw_package.stderr)
-            ));
-        }
+        let package = SwiftPackageDump::load(&swift, &project_path)?;
+        Self::resolve(project_path, toml_config, package, configuration, app)
+    }
+
+    // Everything after reading crosscode.toml and Package.swift, so validation can check the
+    // rest without dumping the package twice
+    pub fn resolve(
+        project_path: PathBuf,
+        toml_config: TomlConfig,
+        package: SwiftPackageDump,
+        configuration: Option<&str>,
+        app: Option<&str>,
+    ) -> Result<Self, String> {
+        toml_config.packaging.validate()?;
+        toml_config.launch_screen.validate()?;
+        let bundle_id = toml_config.bundle_id(configuration, app)?;
+        let configuration = toml_config.configuration(configuration)?;
+        let app = match app {
+            Some(product) => Some(toml_config.app(product)?.clone()),
+            None => None,
+        };
+        let product = match &app {
+            Some(app) => {
+                check_product(&app.product, &package)?;
+                app.product.clone()
+            }
+            None => toml_config.main_product(&package)?,
+        };
+        let platform = PlatformConfig::resolve(
+            toml_config.platform,
+            package.platform_version("ios"),
+            package.platform_version("macos"),
+        )?;
+        let entitlements = match &app {
+            Some(app) => app.entitlements.as_ref(),
+            None => toml_config.entitlements.as_ref(),
+        };
+        let entitlements = match entitlements {
+            Some(entitlements) => Some(load_entitlements(&project_path, entitlements)?),
+            None => None,
+        };
+        // Extensions are embedded in the main app only
+        let extensions = match &app {
+            Some(_) => Vec::new(),
+            None => toml_config
+                .extensions
+                .iter()
+                .map(|ext| ExtensionConfig::resolve(ext, &bundle_id, &package, &project_path))
+                .collect::<Result<Vec<_>, String>>()?,
+        };
+        let info_plist_path = match &app {
+            Some(app) => app.info_plist.as_ref().map(|path| project_path.join(path)),
+            None => Some(project_path.join("Info.plist")),
+        };
 
-        let package: SwiftPackageDump = serde_json::from_slice(&raw_package.stdout)
-            .map_err(|e| format!("Failed to parse package dump: {}", e))?;
+        let apps = toml_config
+            .apps
+            .iter()
+            .map(|app| app.product.clone())
+            .collect();
+
+        let mut info_plist = toml_config.info_plist;
+        if let Some(display_name) = app.as_ref().and_then(|app| app.display_name.as_ref()) {
+            info_plist.insert(
+                "CFBundleDisplayName".to_string(),
+                toml::Value::String(display_name.clone()),
+            );
+        }
+        if let Some(display_name) = &configuration.display_name {
+            info_plist.insert(
+                "CFBundleDisplayName".to_string(),
+                toml::Value::String(display_name.clone()),
+            );
+        }
+        for (key, value) in configuration.info_plist {
+            info_plist.insert(key, value);
+        }
 
         Ok(ProjectConfig {
-            product: package.name,
+            product,
             version_num: toml_config.project.version_num,
             version_string: toml_config.project.version_string,
-            bundle_id: toml_config.project.bundle_id,
+            bundle_id,
             project_path,
+            platform,
+            info_plist,
+            info_plist_path,
+            info_plist_format: toml_config.packaging.info_plist_format.unwrap_or_default(),
+            entitlements,
+            extensions,
+            launch_screen: toml_config.launch_screen,
+            packaging: toml_config.packaging,
+            debug: configuration.debug,
+            build_flags: BuildFlags {
+                swift_conditions: configuration.swift_conditions,
+                swiftc_flags: configuration.swiftc_flags,
+                linker_flags: configuration.linker_flags,
+            },
+            apps,
         })
     }
 
-    pub fn validate(project_path: PathBuf, toolchain_path: &str) -> ProjectValidation {
-        if !project_path.exists() {
-            return ProjectValidation::Invalid;
+    // Every bundle in the packed app that declares entitlements, main app first
+    pub fn bundle_entitlements(&self, app_path: &Path) -> Vec<(PathBuf, Dictionary)> {
+        let mut bundles = Vec::new();
+        if let Some(entitlements) = &self.entitlements {
+            bundles.push((app_path.to_path_buf(), entitlements.clone()));
         }
-        if !project_path.join("Package.swift").exists() {
-            return ProjectValidation::Invalid;
-        }
-        if !project_path.join("crosscode.toml").exists() {
-            return ProjectValidation::Invalid;
-        }
-        let config_res = TomlConfig::load(project_path.clone());
-        // make sure the error isn't unsupported format version
-        if let Err(e) = config_res {
-            if e.contains("Unsupported format version") {
-                return ProjectValidation::UnsupportedFormatVersion;
+        for ext in &self.extensions {
+            if let Some(entitlements) = &ext.entitlements {
+                bundles.push((
+                    app_path.join("PlugIns").join(ext.bundle_name()),
+                    entitlements.clone(),
+                ));
             }
         }
+        bundles
+    }
 
-        let swift = SwiftBin::new(toolchain_path);
-        if let Err(_) = swift {
-            return ProjectValidation::InvalidToolchain;
-        }
-        let swift = swift.unwrap();
+    // CFBundleShortVersionString only allows numbers, a pre-release tag stays in crosscode.toml
+    pub fn short_version(&self) -> &str {
+        self.version_string
+            .split_once('-')
+            .map_or(&self.version_string, |(version, _)| version)
+    }
 
-        let raw_package = swift
-            .command()
-            .arg("package")
-            .arg("dump-package")
-            .current_dir(&project_path)
-            .output();
-        if let Err(_) = raw_package {
-            return ProjectValidation::InvalidToolchain;
+    pub fn target_triple(&self, build_settings: &BuildSettings) -> String {
+        let arch = build_settings.arch.triple_name();
+        match build_settings.platform {
+            TargetPlatform::Ios => format!("{}-apple-ios{}", arch, self.platform.deployment_target),
+            TargetPlatform::IosSimulator => format!(
+                "{}-apple-ios{}-simulator",
+                arch, self.platform.deployment_target
+            ),
+            TargetPlatform::Macos => format!(
+                "{}-apple-macos{}",
+                arch, self.platform.macos_deployment_target
+            ),
         }
-        let raw_package = raw_package.unwrap();
+    }
+}
 
-        if !raw_package.status.success() {
-            return ProjectValidation::InvalidPackage;
+impl ExtensionConfig {
+    fn resolve(
+        toml_config: &ExtensionTomlConfig,
+        app_bundle_id: &str,
+        package: &SwiftPackageDump,
+        project_path: &Path,
+    ) -> Result<Self, String> {
+        let target = package.target(&toml_config.target).ok_or(format!(
+            "Extension target {} not found in Package.swift",
+            toml_config.target
+        ))?;
+        if target.target_type != TargetType::Executable {
+            return Err(format!(
+                "Extension target {} must be an executable target",
+                toml_config.target
+            ));
+        }
+        // Extensions with a principal class are started by NSExtensionMain instead of the
+        // target's main, which only the linker can make the entry point
+        if toml_config.principal_class.is_some() && !links_extension_main(target) {
+            return Err(format!(
+                "Extension target {} has a principal_class, so it must be linked with NSExtensionMain as its entry point. Add linkerSettings: [.unsafeFlags([\"-Xlinker\", \"-e\", \"-Xlinker\", \"_NSExtensionMain\"])] to the target in Package.swift",
+                toml_config.target
+            ));
         }
 
-        let package = serde_json::from_slice::<SwiftPackageDump>(&raw_package.stdout);
-        if let Err(_) = package {
-            return ProjectValidation::InvalidPackage;
+        let suffix = toml_config
+            .bundle_id_suffix
+            .clone()
+            .unwrap_or_else(|| toml_config.target.to_lowercase());
+        let entitlements = match &toml_config.entitlements {
+            Some(entitlements) => Some(load_entitlements(project_path, entitlements)?),
+            None => None,
+        };
+
+        Ok(ExtensionConfig {
+            target: toml_config.target.clone(),
+            extension_point: toml_config.extension_point.clone(),
+            principal_class: toml_config.principal_class.clone(),
+            bundle_id: format!("{}.{}", app_bundle_id, suffix),
+            info_plist: toml_config.info_plist.as_ref().map(PathBuf::from),
+            entitlements,
+        })
+    }
+
+    pub fn bundle_name(&self) -> String {
+        format!("{}.appex", self.target)
+    }
+}
+
+// Whether the target's linker settings set -e _NSExtensionMain for every iOS build
+fn links_extension_main(target: &SwiftPackageTarget) -> bool {
+    target.settings.iter().any(|setting| {
+        let flags: Vec<&str> = setting
+            .values
+            .iter()
+            .map(|v| v.as_str())
+            .filter(|v| *v != "-Xlinker")
+            .collect();
+        setting.tool == "linker"
+            && setting.kind == "unsafeFlags"
+            && setting.configuration.is_none()
+            && (setting.platforms.is_empty() || setting.platforms.iter().any(|p| p == "ios"))
+            && flags.windows(2).any(|w| w == ["-e", "_NSExtensionMain"])
+    })
+}
+
+impl PlatformConfig {
+    fn resolve(
+        toml_config: PlatformTomlConfig,
+        package_ios_version: Option<String>,
+        package_macos_version: Option<String>,
+    ) -> Result<Self, String> {
+        let deployment_target = match (toml_config.deployment_target, &package_ios_version) {
+            (Some(target), _) => target,
+            (None, Some(package_version)) => package_version.clone(),
+            (None, None) => DEFAULT_DEPLOYMENT_TARGET.to_string(),
+        };
+        let target_version = parse_os_version(&deployment_target)
+            .ok_or(format!("Invalid deployment target: {}", deployment_target))?;
+
+        if let Some(package_version) = package_ios_version {
+            let declared = parse_os_version(&package_version).ok_or(format!(
+                "Invalid iOS platform version in Package.swift: {}",
+                package_version
+            ))?;
+            if target_version < declared {
+                return Err(format!(
+                    "Deployment target {} is lower than the iOS {} declared in Package.swift",
+                    deployment_target, package_version
+                ));
+            }
         }
 
-        ProjectValidation::Valid
+        let macos_deployment_target = toml_config
+            .macos_deployment_target
+            .or(package_macos_version)
+            .unwrap_or(DEFAULT_MACOS_DEPLOYMENT_TARGET.to_string());
+        parse_os_version(&macos_deployment_target).ok_or(format!(
+            "Invalid macOS deployment target: {}",
+            macos_deployment_target
+        ))?;
+
+        Ok(PlatformConfig {
+            deployment_target,
+            macos_deployment_target,
+            device_families: toml_config.device_families,
+            orientations: toml_config.orientations,
+            required_capabilities: toml_config.required_capabilities,
+        })
+    }
+}
+
+// "16" and "16.0.0" both become [16, 0, 0] so they compare equal
+fn parse_os_version(version: &str) -> Option<[u32; 3]> {
+    let parts = version
+        .split('.')
+        .map(|p| p.parse::<u32>().ok())
+        .collect::<Option<Vec<u32>>>()?;
+    if parts.len() > 3 {
+        return None;
     }
+    let mut parsed = [0; 3];
+    parsed[..parts.len()].copy_from_slice(&parts);
+    Some(parsed)
 }
 
 impl TomlConfig {
@@ -140,10 +708,103 @@ impl TomlConfig {
                 version_num: "1".to_string(),
                 version_string: "1.0.0".to_string(),
                 bundle_id: bundle_id.to_string(),
+                product: None,
             },
+            platform: PlatformTomlConfig::default(),
+            info_plist: toml::Table::new(),
+            entitlements: None,
+            extensions: Vec::new(),
+            apps: Vec::new(),
+            launch_screen: LaunchScreenConfig::default(),
+            packaging: PackagingConfig::default(),
+            configurations: BTreeMap::new(),
+        }
+    }
+
+    // No configuration means the plain [project] settings
+    pub fn configuration(&self, name: Option<&str>) -> Result<ConfigurationTomlConfig, String> {
+        let name = match name {
+            Some(name) => name,
+            None => return Ok(ConfigurationTomlConfig::default()),
+        };
+        self.configurations.get(name).cloned().ok_or(format!(
+            "Unknown configuration \"{}\", crosscode.toml defines: {}",
+            name,
+            if self.configurations.is_empty() {
+                "none".to_string()
+            } else {
+                self.configurations
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        ))
+    }
+
+    pub fn app(&self, product: &str) -> Result<&AppTomlConfig, String> {
+        self.apps
+            .iter()
+            .find(|app| app.product == product)
+            .ok_or(format!(
+                "Unknown app \"{}\", crosscode.toml defines: {}",
+                product,
+                if self.apps.is_empty() {
+                    "none".to_string()
+                } else {
+                    self.apps
+                        .iter()
+                        .map(|app| app.product.clone())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                }
+            ))
+    }
+
+    // [project] product, or the executable product left once the other apps and extensions
+    // are taken out, preferring the one named after the package
+    pub fn main_product(&self, package: &SwiftPackageDump) -> Result<String, String> {
+        if let Some(product) = &self.project.product {
+            check_product(product, package)?;
+            return Ok(product.clone());
+        }
+        let candidates: Vec<String> = package
+            .executable_products()
+            .into_iter()
+            .filter(|product| {
+                !self.extensions.iter().any(|ext| &ext.target == product)
+                    && !self.apps.iter().any(|app| &app.product == product)
+            })
+            .collect();
+        if candidates.contains(&package.name) {
+            return Ok(package.name.clone());
+        }
+        match candidates.as_slice() {
+            [product] => Ok(product.clone()),
+            [] => Err("Package.swift has no executable product for the app".to_string()),
+            _ => Err(format!(
+                "Package.swift has several executable products ({}), set product in the [project] section of crosscode.toml to choose the app",
+                candidates.join(", ")
+            )),
         }
     }
 
+    // What the app is installed as, before the team ID is appended
+    pub fn bundle_id(
+        &self,
+        configuration: Option<&str>,
+        app: Option<&str>,
+    ) -> Result<String, String> {
+        let bundle_id = match app {
+            Some(product) => self.app(product)?.bundle_id.clone(),
+            None => self.project.bundle_id.clone(),
+        };
+        Ok(match self.configuration(configuration)?.bundle_id_suffix {
+            Some(suffix) => format!("{}.{}", bundle_id, suffix),
+            None => bundle_id,
+        })
+    }
+
     pub fn load_or_default(project_path: PathBuf) -> Result<Self, String> {
         if project_path.exists() {
             Self::load(project_path)
@@ -157,19 +818,136 @@ impl TomlConfig {
     fn load(project_path: PathBuf) -> Result<Self, String> {
         let content = std::fs::read_to_string(project_path.join("crosscode.toml"))
             .map_err(|e| e.to_string())?;
-        let config: TomlConfig = toml::from_str(&content).map_err(|e| e.to_string())?;
-        if config.format_version != FORMAT_VERSION {
+        // Checked before parsing the rest, older files may not match the current layout
+        let format_version = read_format_version(&content)?;
+        if format_version > FORMAT_VERSION {
             return Err(format!(
                 "Unsupported format version: {}, expected: {}",
-                config.format_version, FORMAT_VERSION
+                format_version, FORMAT_VERSION
             ));
         }
-        Ok(config)
+        if format_version < FORMAT_VERSION {
+            return Err(format!(
+                "Outdated format version: {}, migrate crosscode.toml to version {} first",
+                format_version, FORMAT_VERSION
+            ));
+        }
+        toml::from_str(&content).map_err(|e| e.to_string())
     }
 
+    // Changes are merged into the existing file, so comments and formatting survive
     pub fn save(&self, project_path: PathBuf) -> Result<(), String> {
+        let path = project_path.join("crosscode.toml");
         let content = toml::to_string(self).map_err(|e| e.to_string())?;
-        std::fs::write(project_path.join("crosscode.toml"), content).map_err(|e| e.to_string())?;
+        let content = match std::fs::read_to_string(&path) {
+            Ok(existing) => {
+                let mut document = existing
+                    .parse::<DocumentMut>()
+                    .map_err(|e| format!("Failed to parse crosscode.toml: {}", e))?;
+                let new = content.parse::<DocumentMut>().map_err(|e| e.to_string())?;
+                merge_table(document.as_table_mut(), new.as_table());
+                document.to_string()
+            }
+            Err(_) => content,
+        };
+        std::fs::write(path, content).map_err(|e| e.to_string())?;
         Ok(())
     }
 }
+
+fn check_product(product: &str, package: &SwiftPackageDump) -> Result<(), String> {
+    let products = package.executable_products();
+    if !products.iter().any(|p| p == product) {
+        return Err(format!(
+            "Executable product {} not found in Package.swift, executable products: {}",
+            product,
+            if products.is_empty() {
+                "none".to_string()
+            } else {
+                products.join(", ")
+            }
+        ));
+    }
+    Ok(())
+}
+
+fn merge_table(existing: &mut dyn TableLike, new: &dyn TableLike) {
+    let removed: Vec<String> = existing
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !new.contains_key(key))
+        .collect();
+    for key in removed {
+        existing.remove(&key);
+    }
+    for (key, item) in new.iter() {
+        match existing.get_mut(key) {
+            Some(existing_item) => merge_item(existing_item, item),
+            // Tables serde always writes, like [platform], aren't added until they're used
+            None if item.as_table_like().is_some_and(|t| t.is_empty()) => {}
+            None => {
+                existing.insert(key, item.clone());
+            }
+        }
+    }
+}
+
+fn merge_item(existing: &mut Item, new: &Item) {
+    if let (Some(existing), Some(new)) = (existing.as_table_like_mut(), new.as_table_like()) {
+        merge_table(existing, new);
+        return;
+    }
+    if let (Some(existing), Some(new)) =
+        (existing.as_array_of_tables_mut(), new.as_array_of_tables())
+    {
+        while existing.len() > new.len() {
+            existing.remove(existing.len() - 1);
+        }
+        for (index, table) in new.iter().enumerate() {
+            match existing.get_mut(index) {
+                Some(existing) => merge_table(existing, table),
+                None => existing.push(table.clone()),
+            }
+        }
+        return;
+    }
+    match (existing.as_value_mut(), new.as_value()) {
+        // Only values that actually changed are replaced, keeping their comments
+        (Some(existing), Some(new)) => {
+            if !same_value(existing, new) {
+                let decor = existing.decor().clone();
+                *existing = new.clone();
+                *existing.decor_mut() = decor;
+            }
+        }
+        _ => *existing = new.clone(),
+    }
+}
+
+// Compares what the values mean rather than how they're written, e.g. 'a' and "a"
+fn same_value(a: &toml_edit::Value, b: &toml_edit::Value) -> bool {
+    let parse = |value: &toml_edit::Value| format!("value = {}", value).parse::<toml::Table>().ok();
+    parse(a) == parse(b)
+}
+
+// The named configurations the IDE can build, a project without crosscode.toml has none
+#[tauri::command]
+pub fn list_configurations(folder: String) -> Result<Vec<String>, String> {
+    let project_path = PathBuf::from(folder);
+    if !project_path.join("crosscode.toml").exists() {
+        return Ok(Vec::new());
+    }
+    let config = TomlConfig::load(project_path)?;
+    Ok(config.configurations.keys().cloned().collect())
+}
+
+// The products of the [[apps]] the IDE can build besides the main app
+#[tauri::command]
+pub fn list_apps(folder: String) -> Result<Vec<String>, String> {
+    let project_path = PathBuf::from(folder);
+    if !project_path.join("crosscode.toml").exists() {
+        return Ok(Vec::new());
+    }
+    let config = TomlConfig::load(project_path)?;
+    Ok(config.apps.into_iter().map(|app| app.product).collect())
+}
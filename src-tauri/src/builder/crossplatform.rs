@@ -7,8 +7,9 @@ use std::os::windows::process::CommandExt;
 #[cfg(not(target_os = "windows"))]
 use std::path::Path;
 use std::path::PathBuf;
+use std::process::Command;
 #[cfg(target_os = "windows")]
-use std::process::{Command, Stdio};
+use std::process::Stdio;
 
 #[cfg(target_os = "windows")]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
@@ -55,6 +56,21 @@ pub fn symlink(target: &str, link: &str) -> std::io::Result<()> {
     }
 }
 
+// A program from the environment swift runs in, e.g. the git SwiftPM clones with
+pub fn linux_command(program: &str) -> Command {
+    #[cfg(target_os = "windows")]
+    {
+        let mut cmd = Command::new("wsl");
+        cmd.arg(program);
+        cmd.creation_flags(CREATE_NO_WINDOW);
+        cmd
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        Command::new(program)
+    }
+}
+
 pub fn linux_env(key: &str) -> Result<String, String> {
     #[cfg(not(target_os = "windows"))]
     {
@@ -0,0 +1,528 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{Emitter, State};
+
+use crate::{
+    builder::{
+        crossplatform::{linux_command, linux_path},
+        jobs::BuildJobs,
+        manifest::{
+            code_mask, comma_after, indentation, line_end, line_start, manifest_layout,
+            manifest_path, nested_indentation, split_items, starts_line, write_manifest,
+        },
+        package::{DependencyKind, DependencyRequirement, SwiftPackageDump, TargetDependencyKind},
+        swift::{pipe_command, SwiftBin},
+        version::Version,
+    },
+    emit_error_and_return,
+};
+
+// A pin from Package.resolved, what SwiftPM actually checked out
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedDependency {
+    pub identity: String,
+    pub kind: DependencyKind,
+    pub location: String,
+    pub version: Option<String>,
+    pub branch: Option<String>,
+    pub revision: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OutdatedDependency {
+    pub identity: String,
+    pub location: String,
+    pub current: String,
+    // The newest release tag, which may be outside the manifest's requirement
+    pub latest: Option<String>,
+    // The newest release tag `swift package update` would move to
+    pub latest_allowed: Option<String>,
+    pub error: Option<String>,
+}
+
+pub fn read_resolved(project_path: &Path) -> Result<Vec<ResolvedDependency>, String> {
+    let path = project_path.join("Package.resolved");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let json: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse Package.resolved: {}", e))?;
+    parse_resolved(&json)
+}
+
+// Version 1 nests the pins under "object" and only knows git repositories, 2 and 3 list them
+// at the top level with an identity and kind
+fn parse_resolved(json: &Value) -> Result<Vec<ResolvedDependency>, String> {
+    let version = json.get("version").and_then(|v| v.as_u64()).unwrap_or(1);
+    let pins = match version {
+        1 => json.get("object").and_then(|o| o.get("pins")),
+        _ => json.get("pins"),
+    }
+    .and_then(|p| p.as_array())
+    .ok_or("Package.resolved has no pins".to_string())?;
+
+    Ok(pins
+        .iter()
+        .filter_map(|pin| {
+            let (identity, kind, location) = if version == 1 {
+                let location = string(pin, "repositoryURL")?;
+                (
+                    identity_of(&location),
+                    DependencyKind::SourceControl,
+                    location,
+                )
+            } else {
+                let kind = match pin.get("kind")?.as_str()? {
+                    "remoteSourceControl" | "localSourceControl" => DependencyKind::SourceControl,
+                    "fileSystem" => DependencyKind::FileSystem,
+                    "registry" => DependencyKind::Registry,
+                    _ => return None,
+                };
+                (string(pin, "identity")?, kind, string(pin, "location")?)
+            };
+            let state = pin.get("state");
+            Some(ResolvedDependency {
+                identity,
+                kind,
+                location,
+                version: state.and_then(|s| string(s, "version")),
+                branch: state.and_then(|s| string(s, "branch")),
+                revision: state.and_then(|s| string(s, "revision")),
+            })
+        })
+        .collect())
+}
+
+// How SwiftPM names a package it hasn't fetched yet, the last path component without .git
+pub fn identity_of(location: &str) -> String {
+    let name = location
+        .trim_end_matches('/')
+        .rsplit(['/', '\\', ':'])
+        .next()
+        .unwrap_or(location);
+    name.strip_suffix(".git").unwrap_or(name).to_lowercase()
+}
+
+fn swift_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// A .package(...) entry. Without a requirement the location is used as a local package path,
+// with one it's fetched as a git repository, which may also be a local path
+fn package_entry(location: &str, requirement: Option<&DependencyRequirement>) -> String {
+    let Some(requirement) = requirement else {
+        return format!(".package(path: {})", swift_string(location));
+    };
+    let requirement = match requirement {
+        DependencyRequirement::Range {
+            lower_bound,
+            upper_bound,
+        } => {
+            let up_to_next_major = Version::parse(lower_bound)
+                .ok()
+                .zip(Version::parse(upper_bound).ok())
+                .is_some_and(|(lower, upper)| {
+                    upper
+                        == Version {
+                            major: lower.major + 1,
+                            minor: 0,
+                            patch: 0,
+                            pre_release: None,
+                        }
+                });
+            if up_to_next_major {
+                format!("from: {}", swift_string(lower_bound))
+            } else {
+                format!(
+                    "{}..<{}",
+                    swift_string(lower_bound),
+                    swift_string(upper_bound)
+                )
+            }
+        }
+        DependencyRequirement::Exact { version } => format!("exact: {}", swift_string(version)),
+        DependencyRequirement::Branch { name } => format!("branch: {}", swift_string(name)),
+        DependencyRequirement::Revision { revision } => {
+            format!("revision: {}", swift_string(revision))
+        }
+    };
+    format!(".package(url: {}, {})", swift_string(location), requirement)
+}
+
+pub fn add_manifest_dependency(source: &str, entry: &str) -> Result<String, String> {
+    let mask = code_mask(source);
+    let layout = manifest_layout(&mask)?;
+    let mut edited = source.to_string();
+
+    let Some((open, close)) = layout.array_argument(source, &mask, "dependencies")? else {
+        // SwiftPM wants its arguments in order, dependencies go right before targets
+        let (targets, _) = layout
+            .argument(source, "targets")
+            .ok_or("Package.swift has no targets to add dependencies before".to_string())?;
+        if !starts_line(source, targets) {
+            edited.insert_str(targets, &format!("dependencies: [{}], ", entry));
+            return Ok(edited);
+        }
+        let indent = indentation(source, targets);
+        let nested = nested_indentation(source, indent, layout.package_start);
+        edited.insert_str(
+            line_start(source, targets),
+            &format!(
+                "{indent}dependencies: [\n{indent}{nested}{entry},\n{indent}],\n",
+                indent = indent,
+                nested = nested,
+                entry = entry
+            ),
+        );
+        return Ok(edited);
+    };
+
+    let items = split_items(&mask, open + 1, close);
+    let multi_line = source[open..close].contains('\n');
+    match items.last() {
+        Some(&(_, last_end)) if multi_line => {
+            let indent = indentation(source, items[items.len() - 1].0);
+            let comma = comma_after(&mask, last_end, close);
+            // After the rest of the last entry's line, so a trailing comment stays with it
+            let end = line_end(source, comma.unwrap_or(last_end));
+            if end < close {
+                edited.insert_str(end, &format!("\n{}{},", indent, entry));
+            } else {
+                edited.insert_str(close, &format!("\n{}{}", indent, entry));
+            }
+            if comma.is_none() {
+                edited.insert(last_end, ',');
+            }
+        }
+        Some(&(_, last_end)) => edited.insert_str(last_end, &format!(", {}", entry)),
+        None if multi_line => {
+            let indent = indentation(source, open);
+            let nested = nested_indentation(source, indent, layout.package_start);
+            edited.insert_str(open + 1, &format!("\n{}{}{},", indent, nested, entry));
+        }
+        // An empty array stays inline unless the argument has a line of its own
+        None if !source[line_start(source, open)..open]
+            .trim_start()
+            .starts_with("dependencies") =>
+        {
+            edited.insert_str(open + 1, entry)
+        }
+        None => {
+            let indent = indentation(source, open);
+            let nested = nested_indentation(source, indent, layout.package_start);
+            edited.replace_range(
+                open..=close,
+                &format!("[\n{}{}{},\n{}]", indent, nested, entry, indent),
+            );
+        }
+    }
+    Ok(edited)
+}
+
+// Removes the entry whose url or path is the location, or whose identity matches
+pub fn remove_manifest_dependency(source: &str, dependency: &str) -> Result<String, String> {
+    let mask = code_mask(source);
+    let layout = manifest_layout(&mask)?;
+    let (open, close) = layout
+        .array_argument(source, &mask, "dependencies")?
+        .ok_or("Package.swift has no dependencies".to_string())?;
+    let items = split_items(&mask, open + 1, close);
+    let location = Regex::new(r#"\b(?:url|path)\s*:\s*"((?:[^"\\]|\\.)*)""#).unwrap();
+    let wanted = dependency.trim_end_matches('/');
+    let index = items
+        .iter()
+        .position(|(s, e)| {
+            location
+                .captures(&source[*s..*e])
+                .map(|c| c[1].replace("\\\\", "\\").replace("\\\"", "\""))
+                .is_some_and(|found| {
+                    found.trim_end_matches('/') == wanted
+                        || identity_of(&found) == identity_of(wanted)
+                })
+        })
+        .ok_or(format!(
+            "{} is not a dependency in Package.swift",
+            dependency
+        ))?;
+
+    let (start, end) = items[index];
+    let after_comma = comma_after(&mask, end, close);
+    let after = after_comma.unwrap_or(end);
+    let mut edited = source.to_string();
+    if starts_line(source, start)
+        && line_end(source, end) < close
+        && mask[after..line_end(source, end)]
+            .iter()
+            .all(|b| b.is_ascii_whitespace())
+    {
+        // The entry's whole lines, along with a comment after it
+        edited.replace_range(line_start(source, start)..line_end(source, end) + 1, "");
+    } else if let Some(after_comma) = after_comma {
+        let next = after_comma
+            + mask[after_comma..close]
+                .iter()
+                .take_while(|b| **b == b' ')
+                .count();
+        edited.replace_range(start..next, "");
+    } else if index > 0 {
+        edited.replace_range(items[index - 1].1..end, "");
+    } else {
+        edited.replace_range(start..end, "");
+    }
+    Ok(edited)
+}
+
+pub fn add_dependency_to_project(
+    swift: &SwiftBin,
+    project_path: &Path,
+    location: &str,
+    requirement: Option<&DependencyRequirement>,
+) -> Result<SwiftPackageDump, String> {
+    let identity = identity_of(location);
+    let package = SwiftPackageDump::load(swift, project_path)?;
+    if package.dependencies.iter().any(|d| d.identity == identity) {
+        return Err(format!("{} is already a dependency", identity));
+    }
+    let original = fs::read_to_string(manifest_path(project_path))
+        .map_err(|e| format!("Failed to read Package.swift: {}", e))?;
+    let edited = add_manifest_dependency(&original, &package_entry(location, requirement))?;
+    write_manifest(swift, project_path, &original, &edited, |package| {
+        if package.dependencies.iter().any(|d| d.identity == identity) {
+            Ok(())
+        } else {
+            Err(format!("{} was not added to Package.swift", identity))
+        }
+    })
+}
+
+pub fn remove_dependency_from_project(
+    swift: &SwiftBin,
+    project_path: &Path,
+    dependency: &str,
+) -> Result<SwiftPackageDump, String> {
+    let package = SwiftPackageDump::load(swift, project_path)?;
+    let identity = package
+        .dependencies
+        .iter()
+        .find(|d| d.location == dependency || d.identity == identity_of(dependency))
+        .map(|d| d.identity.clone())
+        .ok_or(format!("{} is not a dependency", dependency))?;
+    let users: Vec<&str> = package
+        .targets
+        .iter()
+        .filter(|target| {
+            target.dependencies.iter().any(|d| {
+                d.kind == TargetDependencyKind::Product
+                    && d.package
+                        .as_ref()
+                        .is_some_and(|p| p.eq_ignore_ascii_case(&identity))
+            })
+        })
+        .map(|target| target.name.as_str())
+        .collect();
+    if !users.is_empty() {
+        return Err(format!(
+            "{} is still used by {}, remove it from their dependencies first",
+            identity,
+            users.join(", ")
+        ));
+    }
+
+    let original = fs::read_to_string(manifest_path(project_path))
+        .map_err(|e| format!("Failed to read Package.swift: {}", e))?;
+    let edited = remove_manifest_dependency(&original, dependency)?;
+    write_manifest(swift, project_path, &original, &edited, |package| {
+        if package.dependencies.iter().any(|d| d.identity == identity) {
+            Err(format!("{} was not removed from Package.swift", identity))
+        } else {
+            Ok(())
+        }
+    })
+}
+
+// Release versions among a git repository's tags, the same ones SwiftPM resolves against
+fn release_tags(location: &str) -> Result<Vec<Version>, String> {
+    let output = linux_command("git")
+        .args(["ls-remote", "--tags", "--refs", location])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .map_err(|e| format!("Failed to execute git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to list tags: {}",
+            String::from_utf8_lossy(&output.stderr)
+                .lines()
+                .next()
+                .unwrap_or_default()
+        ));
+    }
+    let mut versions: Vec<Version> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once("refs/tags/"))
+        .filter_map(|(_, tag)| Version::parse(tag.strip_prefix('v').unwrap_or(tag)).ok())
+        .filter(|version| version.pre_release.is_none())
+        .collect();
+    versions.sort();
+    Ok(versions)
+}
+
+pub fn outdated_dependencies(
+    swift: &SwiftBin,
+    project_path: &Path,
+) -> Result<Vec<OutdatedDependency>, String> {
+    let resolved = read_resolved(project_path)?;
+    // Only needed for the requirements, the tags are still worth checking without it
+    let package = SwiftPackageDump::load(swift, project_path).ok();
+    let mut outdated = Vec::new();
+    for pin in resolved {
+        let Some(current) = pin.version.as_ref().and_then(|v| Version::parse(v).ok()) else {
+            continue;
+        };
+        if pin.kind != DependencyKind::SourceControl {
+            continue;
+        }
+        let requirement = package
+            .as_ref()
+            .and_then(|p| p.dependencies.iter().find(|d| d.identity == pin.identity))
+            .and_then(|d| d.requirement.clone());
+        let mut entry = OutdatedDependency {
+            identity: pin.identity.clone(),
+            location: pin.location.clone(),
+            current: current.to_string(),
+            latest: None,
+            latest_allowed: None,
+            error: None,
+        };
+        match release_tags(&pin.location) {
+            Ok(tags) => {
+                let Some(latest) = tags.last().filter(|latest| **latest > current) else {
+                    continue;
+                };
+                entry.latest = Some(latest.to_string());
+                if let Some(DependencyRequirement::Range { upper_bound, .. }) = &requirement {
+                    entry.latest_allowed = Version::parse(upper_bound).ok().and_then(|upper| {
+                        tags.iter()
+                            .rfind(|tag| **tag > current && **tag < upper)
+                            .map(|tag| tag.to_string())
+                    });
+                }
+            }
+            Err(e) => entry.error = Some(e),
+        }
+        outdated.push(entry);
+    }
+    Ok(outdated)
+}
+
+fn string(value: &Value, key: &str) -> Option<String> {
+    value.get(key)?.as_str().map(|s| s.to_string())
+}
+
+#[tauri::command]
+pub fn list_dependencies(folder: String) -> Result<Vec<ResolvedDependency>, String> {
+    read_resolved(&PathBuf::from(folder))
+}
+
+#[tauri::command]
+pub fn add_dependency(
+    folder: String,
+    toolchain_path: String,
+    location: String,
+    requirement: Option<DependencyRequirement>,
+) -> Result<SwiftPackageDump, String> {
+    let swift = SwiftBin::new(&toolchain_path)?;
+    // Local paths are read by SwiftPM inside WSL on Windows
+    let location = if Path::new(&location).is_absolute() {
+        linux_path(&location)?
+    } else {
+        location
+    };
+    add_dependency_to_project(
+        &swift,
+        &PathBuf::from(folder),
+        &location,
+        requirement.as_ref(),
+    )
+}
+
+#[tauri::command]
+pub fn remove_dependency(
+    folder: String,
+    toolchain_path: String,
+    dependency: String,
+) -> Result<SwiftPackageDump, String> {
+    let swift = SwiftBin::new(&toolchain_path)?;
+    remove_dependency_from_project(&swift, &PathBuf::from(folder), &dependency)
+}
+
+#[tauri::command]
+pub async fn check_outdated_dependencies(
+    folder: String,
+    toolchain_path: String,
+) -> Result<Vec<OutdatedDependency>, String> {
+    let swift = SwiftBin::new(&toolchain_path)?;
+    outdated_dependencies(&swift, &PathBuf::from(folder))
+}
+
+#[tauri::command]
+pub async fn resolve_packages(
+    window: tauri::Window,
+    jobs: State<'_, BuildJobs>,
+    folder: String,
+    toolchain_path: String,
+) -> Result<(), String> {
+    let job = match jobs.start(&window, &folder, "resolve") {
+        Ok(job) => job,
+        Err(e) => return emit_error_and_return(&window, &e),
+    };
+    let swift_bin = SwiftBin::new(&toolchain_path)?;
+    let mut cmd = swift_bin.build_command();
+    cmd.arg("package").arg("resolve").current_dir(folder);
+
+    window
+        .emit("build-output", "Resolving packages...")
+        .expect("failed to send output");
+
+    pipe_command(&mut cmd, &window, true, Some(job.process())).await?;
+    job.succeed();
+    Ok(())
+}
+
+// Updates the given packages, or all of them if none are given
+#[tauri::command]
+pub async fn update_packages(
+    window: tauri::Window,
+    jobs: State<'_, BuildJobs>,
+    folder: String,
+    toolchain_path: String,
+    packages: Vec<String>,
+) -> Result<(), String> {
+    let job = match jobs.start(&window, &folder, "update") {
+        Ok(job) => job,
+        Err(e) => return emit_error_and_return(&window, &e),
+    };
+    let swift_bin = SwiftBin::new(&toolchain_path)?;
+    let mut cmd = swift_bin.build_command();
+    cmd.arg("package")
+        .arg("update")
+        .args(packages)
+        .current_dir(folder);
+
+    window
+        .emit("build-output", "Updating packages...")
+        .expect("failed to send output");
+
+    pipe_command(&mut cmd, &window, true, Some(job.process())).await?;
+    job.succeed();
+    Ok(())
+}
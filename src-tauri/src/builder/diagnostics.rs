@@ -0,0 +1,271 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::{Emitter, Window};
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+    Remark,
+}
+
+#[derive(Serialize, Clone, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticRange {
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
+#[derive(Serialize, Clone, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    pub severity: Severity,
+    // The tool that reported it when there is no source location, e.g. "ld64.lld"
+    pub tool: Option<String>,
+    pub file: Option<String>,
+    pub range: Option<DiagnosticRange>,
+    pub message: String,
+    pub fix_its: Vec<String>,
+    pub notes: Vec<Diagnostic>,
+}
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticSummary {
+    pub errors: u32,
+    pub warnings: u32,
+    pub exit_code: i32,
+}
+
+// Shared by the stdout and stderr readers of one command, so the summary covers both and
+// the diagnostics swiftc repeats for every file in a batch are only reported once
+#[derive(Clone)]
+pub struct DiagnosticCollector {
+    window: Window,
+    seen: Arc<Mutex<HashSet<Diagnostic>>>,
+}
+
+impl DiagnosticCollector {
+    pub fn new(window: Window) -> Self {
+        DiagnosticCollector {
+            window,
+            seen: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    fn report(&self, diagnostic: Diagnostic) {
+        let mut seen = self.seen.lock().unwrap();
+        if seen.contains(&diagnostic) {
+            return;
+        }
+        self.window.emit("build-diagnostic", &diagnostic).ok();
+        seen.insert(diagnostic);
+    }
+
+    pub fn finish(&self, exit_code: i32) -> DiagnosticSummary {
+        let seen = self.seen.lock().unwrap();
+        let mut summary = DiagnosticSummary {
+            exit_code,
+            ..Default::default()
+        };
+        for diagnostic in seen.iter() {
+            match diagnostic.severity {
+                Severity::Error => summary.errors += 1,
+                Severity::Warning => summary.warnings += 1,
+                _ => {}
+            }
+        }
+        self.window.emit("build-diagnostics-summary", &summary).ok();
+        summary
+    }
+}
+
+enum Expect {
+    // A header was just read, the llvm style follows it with the source line
+    SourceLine,
+    Caret,
+    FixIt { indent: usize },
+    Nothing,
+}
+
+// Understands the llvm diagnostic style used by swiftc (with -diagnostic-style llvm),
+// clang and the linkers:
+//   file:line:col: severity: message
+//   <source line>
+//   <caret and ~ range>
+//   <optional fix-it replacement>
+pub struct DiagnosticParser {
+    collector: DiagnosticCollector,
+    located: Regex,
+    tool: Regex,
+    current: Option<Diagnostic>,
+    expect: Expect,
+}
+
+impl DiagnosticParser {
+    pub fn new(collector: DiagnosticCollector) -> Self {
+        DiagnosticParser {
+            collector,
+            located: Regex::new(r"^(.+?):(\d+):(?:(\d+):)? (error|warning|note|remark): (.*)$")
+                .unwrap(),
+            tool: Regex::new(r"^(?:([\w.\-]+): )?(error|warning|note): (.*)$").unwrap(),
+            current: None,
+            expect: Expect::Nothing,
+        }
+    }
+
+    pub fn feed(&mut self, line: &str) {
+        if let Some(diagnostic) = self.parse_header(line) {
+            if diagnostic.severity == Severity::Note {
+                if let Some(parent) = self.current.as_mut() {
+                    parent.notes.push(diagnostic);
+                    self.expect = Expect::SourceLine;
+                    return;
+                }
+            }
+            self.flush();
+            self.current = Some(diagnostic);
+            self.expect = Expect::SourceLine;
+            return;
+        }
+
+        let current = match self.current.as_mut() {
+            Some(current) => current,
+            None => return,
+        };
+        // lld explains undefined symbols on the lines after the error
+        if let Some(reference) = line.strip_prefix(">>> ") {
+            let note = tool_note(reference, &current.tool);
+            current.notes.push(note);
+            return;
+        }
+        // Snippets belong to the last note if there is one
+        let target = if current.notes.is_empty() {
+            current
+        } else {
+            current.notes.last_mut().unwrap()
+        };
+        match self.expect {
+            Expect::SourceLine if target.range.is_some() && !line.trim().is_empty() => {
+                self.expect = Expect::Caret;
+            }
+            Expect::Caret if is_caret_line(line) => {
+                let start = line.find(|c| c != ' ').unwrap_or(0);
+                let end = line.trim_end().len();
+                if let Some(range) = target.range.as_mut() {
+                    range.start_column = start as u32 + 1;
+                    range.end_column = end.max(start + 1) as u32;
+                }
+                self.expect = Expect::FixIt { indent: start };
+            }
+            // The replacement text starts exactly under the caret, anything else is unrelated
+            Expect::FixIt { indent }
+                if !line.trim().is_empty()
+                    && line.len() - line.trim_start().len() == indent
+                    && !self.tool.is_match(line.trim_start())
+                    && !is_progress_line(line) =>
+            {
+                target.fix_its.push(line.trim().to_string());
+                self.expect = Expect::Nothing;
+            }
+            _ => {
+                self.flush();
+            }
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if let Some(diagnostic) = self.current.take() {
+            self.collector.report(diagnostic);
+        }
+        self.expect = Expect::Nothing;
+    }
+
+    fn parse_header(&self, line: &str) -> Option<Diagnostic> {
+        if let Some(captures) = self.located.captures(line) {
+            let line_number: u32 = captures[2].parse().ok()?;
+            let column: u32 = captures
+                .get(3)
+                .and_then(|c| c.as_str().parse().ok())
+                .unwrap_or(1);
+            return Some(Diagnostic {
+                severity: parse_severity(&captures[4]),
+                tool: None,
+                file: Some(captures[1].to_string()),
+                range: Some(DiagnosticRange {
+                    start_line: line_number,
+                    start_column: column,
+                    end_line: line_number,
+                    end_column: column,
+                }),
+                message: captures[5].to_string(),
+                fix_its: Vec::new(),
+                notes: Vec::new(),
+            });
+        }
+        let captures = self.tool.captures(line)?;
+        Some(Diagnostic {
+            severity: parse_severity(&captures[2]),
+            tool: captures.get(1).map(|t| t.as_str().to_string()),
+            file: None,
+            range: None,
+            message: captures[3].to_string(),
+            fix_its: Vec::new(),
+            notes: Vec::new(),
+        })
+    }
+}
+
+fn parse_severity(severity: &str) -> Severity {
+    match severity {
+        "error" => Severity::Error,
+        "warning" => Severity::Warning,
+        "remark" => Severity::Remark,
+        _ => Severity::Note,
+    }
+}
+
+fn is_caret_line(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.contains('^') && trimmed.chars().all(|c| c == '^' || c == '~' || c == ' ')
+}
+
+// SwiftPM and xcbuild status output, e.g. "[3/12] Compiling App main.swift"
+fn is_progress_line(line: &str) -> bool {
+    const PREFIXES: &[&str] = &[
+        "[",
+        "Building ",
+        "Build complete",
+        "Compiling ",
+        "Linking ",
+        "Emitting ",
+        "Write ",
+        "Fetching ",
+        "Computing ",
+        "Resolving ",
+        "Planning ",
+    ];
+    let trimmed = line.trim_start();
+    PREFIXES.iter().any(|prefix| trimmed.starts_with(prefix))
+}
+
+fn tool_note(message: &str, tool: &Option<String>) -> Diagnostic {
+    Diagnostic {
+        severity: Severity::Note,
+        tool: tool.clone(),
+        file: None,
+        range: None,
+        message: message.to_string(),
+        fix_its: Vec::new(),
+        notes: Vec::new(),
+    }
+}
@@ -0,0 +1,175 @@
+use std::path::Path;
+
+use plist::{Dictionary, Value};
+use serde::{Deserialize, Serialize};
+
+use crate::builder::info_plist::toml_to_plist;
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+pub enum EntitlementsConfig {
+    Path(String),
+    Inline(toml::Table),
+}
+
+pub struct Capability {
+    pub entitlement: &'static str,
+    // Developer portal feature that must be enabled on the App ID, if any
+    pub feature: Option<&'static str>,
+    pub free_account: bool,
+}
+
+pub const CAPABILITIES: &[Capability] = &[
+    Capability {
+        entitlement: "com.apple.security.application-groups",
+        feature: Some("APG3427HIY"),
+        free_account: true,
+    },
+    Capability {
+        entitlement: "keychain-access-groups",
+        feature: None,
+        free_account: true,
+    },
+    Capability {
+        entitlement: "com.apple.developer.kernel.increased-memory-limit",
+        feature: None,
+        free_account: true,
+    },
+    Capability {
+        entitlement: "get-task-allow",
+        feature: None,
+        free_account: true,
+    },
+    Capability {
+        entitlement: "inter-app-audio",
+        feature: Some("IAD53UNK2F"),
+        free_account: true,
+    },
+    Capability {
+        entitlement: "com.apple.developer.homekit",
+        feature: Some("homeKit"),
+        free_account: true,
+    },
+    Capability {
+        entitlement: "com.apple.developer.healthkit",
+        feature: Some("HK421J6T7P"),
+        free_account: true,
+    },
+    Capability {
+        entitlement: "com.apple.developer.associated-domains",
+        feature: Some("SKC3T5S89Y"),
+        free_account: false,
+    },
+    Capability {
+        entitlement: "aps-environment",
+        feature: Some("push"),
+        free_account: false,
+    },
+    Capability {
+        entitlement: "com.apple.developer.icloud-container-identifiers",
+        feature: Some("iCloud"),
+        free_account: false,
+    },
+    Capability {
+        entitlement: "com.apple.developer.icloud-services",
+        feature: Some("iCloud"),
+        free_account: false,
+    },
+    Capability {
+        entitlement: "com.apple.developer.ubiquity-kvstore-identifier",
+        feature: Some("iCloud"),
+        free_account: false,
+    },
+    Capability {
+        entitlement: "com.apple.developer.siri",
+        feature: Some("SI015DKUHP"),
+        free_account: false,
+    },
+    Capability {
+        entitlement: "com.apple.developer.networking.networkextension",
+        feature: Some("NWEXT04537"),
+        free_account: false,
+    },
+    Capability {
+        entitlement: "com.apple.developer.nfc.readersession.formats",
+        feature: Some("NFCTRMAY17"),
+        free_account: false,
+    },
+];
+
+pub fn capability(entitlement: &str) -> Option<&'static Capability> {
+    CAPABILITIES.iter().find(|c| c.entitlement == entitlement)
+}
+
+pub fn load_entitlements(
+    project_path: &Path,
+    config: &EntitlementsConfig,
+) -> Result<Dictionary, String> {
+    let entitlements = match config {
+        EntitlementsConfig::Path(path) => {
+            let path = project_path.join(path);
+            let value = Value::from_file(&path).map_err(|e| {
+                format!("Failed to read entitlements file {}: {}", path.display(), e)
+            })?;
+            match value {
+                Value::Dictionary(dict) => dict,
+                _ => {
+                    return Err(format!(
+                        "Entitlements file {} is not a dictionary",
+                        path.display()
+                    ))
+                }
+            }
+        }
+        EntitlementsConfig::Inline(table) => table
+            .iter()
+            .map(|(k, v)| (k.clone(), toml_to_plist(v)))
+            .collect(),
+    };
+    validate_entitlements(&entitlements)?;
+    Ok(entitlements)
+}
+
+fn validate_entitlements(entitlements: &Dictionary) -> Result<(), String> {
+    for (key, value) in entitlements {
+        match key.as_str() {
+            "com.apple.security.application-groups" => {
+                for group in string_array(key, value)? {
+                    if !group.starts_with("group.") {
+                        return Err(format!(
+                            "App group \"{}\" must start with \"group.\"",
+                            group
+                        ));
+                    }
+                }
+            }
+            "com.apple.developer.associated-domains" => {
+                for domain in string_array(key, value)? {
+                    if !domain.contains(':') {
+                        return Err(format!(
+                            "Associated domain \"{}\" must have a service prefix such as \"applinks:\"",
+                            domain
+                        ));
+                    }
+                }
+            }
+            "keychain-access-groups" | "com.apple.developer.icloud-container-identifiers" => {
+                string_array(key, value)?;
+            }
+            "com.apple.developer.kernel.increased-memory-limit" | "get-task-allow"
+                if value.as_boolean().is_none() =>
+            {
+                return Err(format!("Entitlement {} must be a boolean", key));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn string_array<'a>(key: &str, value: &'a Value) -> Result<Vec<&'a str>, String> {
+    value
+        .as_array()
+        .and_then(|items| items.iter().map(|i| i.as_string()).collect())
+        .ok_or(format!("Entitlement {} must be an array of strings", key))
+}
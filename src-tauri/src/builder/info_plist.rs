@@ -0,0 +1,461 @@
+use std::{fs, path::Path};
+
+use plist::{Dictionary, Value};
+
+use crate::builder::config::{
+    ExtensionConfig, PlatformConfig, PlistFormat, ProjectConfig, TargetPlatform,
+};
+
+// Keys that would make macOS treat the bundle as an iOS app
+const IOS_ONLY_KEYS: &[&str] = &[
+    "MinimumOSVersion",
+    "LSRequiresIPhoneOS",
+    "UIDeviceFamily",
+    "UIRequiredDeviceCapabilities",
+    "UILaunchScreen",
+    "UILaunchStoryboardName",
+];
+
+pub fn build_info_plist(
+    project_path: &Path,
+    config: &ProjectConfig,
+    target: TargetPlatform,
+) -> Result<Dictionary, String> {
+    let mut info = match &config.info_plist_path {
+        Some(path) => load_user_plist(path, config)?,
+        None => Dictionary::new(),
+    };
+
+    for (key, value) in &config.info_plist {
+        info.insert(key.clone(), toml_to_plist(value));
+    }
+
+    let mut generated = generated_keys(config, target);
+    let mut defaults = default_keys(config);
+    if target == TargetPlatform::Macos {
+        return merge_generated(info, generated, defaults).map(strip_ios_keys);
+    }
+    // A storyboard can only be used if a precompiled .storyboardc was shipped in Resources/
+    let has_storyboard = info
+        .get("UILaunchStoryboardName")
+        .and_then(Value::as_string)
+        .is_some_and(|name| {
+            project_path
+                .join("Resources")
+                .join(format!("{}.storyboardc", name))
+                .exists()
+        });
+    if config.launch_screen.is_configured() {
+        info.remove("UILaunchStoryboardName");
+        generated.push((
+            "UILaunchScreen".to_string(),
+            config.launch_screen.plist_value(),
+        ));
+    } else if !has_storyboard {
+        info.remove("UILaunchStoryboardName");
+        // Even an empty UILaunchScreen opts out of the letterboxed legacy launch
+        defaults.push((
+            "UILaunchScreen".to_string(),
+            Value::Dictionary(Dictionary::new()),
+        ));
+    }
+
+    merge_generated(info, generated, defaults)
+}
+
+pub fn build_extension_info_plist(
+    project_path: &Path,
+    config: &ProjectConfig,
+    extension: &ExtensionConfig,
+    target: TargetPlatform,
+) -> Result<Dictionary, String> {
+    let info = match &extension.info_plist {
+        Some(path) => load_user_plist(&project_path.join(path), config)?,
+        None => Dictionary::new(),
+    };
+
+    let mut info = merge_generated(
+        info,
+        extension_generated_keys(config, extension, target),
+        extension_default_keys(extension),
+    )?;
+    if target == TargetPlatform::Macos {
+        info = strip_ios_keys(info);
+    }
+
+    // NSExtensionAttributes and friends are left to the user's plist
+    if !info.contains_key("NSExtension") {
+        info.insert(
+            "NSExtension".to_string(),
+            Value::Dictionary(Dictionary::new()),
+        );
+    }
+    let ns_extension = info
+        .get_mut("NSExtension")
+        .and_then(Value::as_dictionary_mut)
+        .ok_or(format!(
+            "NSExtension in the {} Info.plist is not a dictionary",
+            extension.target
+        ))?;
+    let mut generated = vec![(
+        "NSExtensionPointIdentifier".to_string(),
+        Value::String(extension.extension_point.clone()),
+    )];
+    if let Some(principal_class) = &extension.principal_class {
+        generated.push((
+            "NSExtensionPrincipalClass".to_string(),
+            Value::String(principal_class.clone()),
+        ));
+    }
+    let conflicts = find_conflicts(ns_extension, &generated);
+    if !conflicts.is_empty() {
+        return Err(format!(
+            "{} Info.plist conflicts with crosscode.toml:\n{}",
+            extension.target,
+            conflicts.join("\n")
+        ));
+    }
+    for (key, value) in generated {
+        ns_extension.insert(key, value);
+    }
+
+    Ok(info)
+}
+
+fn merge_generated(
+    mut info: Dictionary,
+    generated: Vec<(String, Value)>,
+    defaults: Vec<(String, Value)>,
+) -> Result<Dictionary, String> {
+    let stray = find_placeholders(&info);
+    if !stray.is_empty() {
+        return Err(format!(
+            "Info.plist contains unresolved placeholders in: {}",
+            stray.join(", ")
+        ));
+    }
+
+    let conflicts = find_conflicts(&info, &generated);
+    if !conflicts.is_empty() {
+        return Err(format!(
+            "Info.plist conflicts with crosscode.toml:\n{}",
+            conflicts.join("\n")
+        ));
+    }
+
+    for (key, value) in generated {
+        info.insert(key, value);
+    }
+    for (key, value) in defaults {
+        if !info.contains_key(&key) {
+            info.insert(key, value);
+        }
+    }
+
+    Ok(info)
+}
+
+fn find_conflicts(info: &Dictionary, generated: &[(String, Value)]) -> Vec<String> {
+    generated
+        .iter()
+        .filter_map(|(key, value)| match info.get(key) {
+            Some(existing) if existing != value => Some(format!(
+                "{} is {} but crosscode.toml requires {}",
+                key,
+                describe(existing),
+                describe(value)
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn write_info_plist(info: &Dictionary, path: &Path, format: PlistFormat) -> Result<(), String> {
+    let value = Value::Dictionary(info.clone());
+    match format {
+        PlistFormat::Xml => value.to_file_xml(path),
+        PlistFormat::Binary => value.to_file_binary(path),
+    }
+    .map_err(|e| format!("Failed to write Info.plist: {}", e))
+}
+
+fn load_user_plist(path: &Path, config: &ProjectConfig) -> Result<Dictionary, String> {
+    if !path.exists() {
+        return Ok(Dictionary::new());
+    }
+
+    let mut content =
+        fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    // Placeholders from the original templates, still substituted so older projects keep building
+    if let Ok(text) = String::from_utf8(content.clone()) {
+        content = text
+            .replace("[[bundle_id]]", &config.bundle_id)
+            .replace("[[product]]", &config.product)
+            .replace("[[version_num]]", &config.version_num)
+            .replace("[[version_string]]", config.short_version())
+            .into_bytes();
+    }
+
+    let value = Value::from_reader(std::io::Cursor::new(content))
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    match value {
+        Value::Dictionary(dict) => Ok(dict),
+        _ => Err(format!("{} root is not a dictionary", path.display())),
+    }
+}
+
+// Keys derived from crosscode.toml, the user's Info.plist may only repeat them with the same value
+fn generated_keys(config: &ProjectConfig, target: TargetPlatform) -> Vec<(String, Value)> {
+    let mut keys = vec![
+        (
+            "CFBundleExecutable".to_string(),
+            Value::String(config.product.clone()),
+        ),
+        (
+            "CFBundleIdentifier".to_string(),
+            Value::String(config.bundle_id.clone()),
+        ),
+        (
+            "CFBundleVersion".to_string(),
+            Value::String(config.version_num.clone()),
+        ),
+        (
+            "CFBundleShortVersionString".to_string(),
+            Value::String(config.short_version().to_string()),
+        ),
+        (
+            "CFBundleSupportedPlatforms".to_string(),
+            Value::Array(vec![Value::String(target.plist_name().to_string())]),
+        ),
+    ];
+    if target == TargetPlatform::Macos {
+        keys.push(macos_version_key(&config.platform));
+    } else {
+        keys.extend(platform_keys(&config.platform));
+    }
+    keys
+}
+
+fn extension_generated_keys(
+    config: &ProjectConfig,
+    extension: &ExtensionConfig,
+    target: TargetPlatform,
+) -> Vec<(String, Value)> {
+    // Versions have to match the containing app or installation fails
+    let mut keys = vec![
+        (
+            "CFBundleExecutable".to_string(),
+            Value::String(extension.target.clone()),
+        ),
+        (
+            "CFBundleIdentifier".to_string(),
+            Value::String(extension.bundle_id.clone()),
+        ),
+        (
+            "CFBundleVersion".to_string(),
+            Value::String(config.version_num.clone()),
+        ),
+        (
+            "CFBundleShortVersionString".to_string(),
+            Value::String(config.short_version().to_string()),
+        ),
+        (
+            "CFBundlePackageType".to_string(),
+            Value::String("XPC!".to_string()),
+        ),
+        (
+            "CFBundleSupportedPlatforms".to_string(),
+            Value::Array(vec![Value::String(target.plist_name().to_string())]),
+        ),
+    ];
+    if target == TargetPlatform::Macos {
+        keys.push(macos_version_key(&config.platform));
+        return keys;
+    }
+    keys.push((
+        "MinimumOSVersion".to_string(),
+        Value::String(config.platform.deployment_target.clone()),
+    ));
+    if !config.platform.device_families.is_empty() {
+        keys.push((
+            "UIDeviceFamily".to_string(),
+            Value::Array(
+                config
+                    .platform
+                    .device_families
+                    .iter()
+                    .map(|f| Value::Integer(f.plist_value().into()))
+                    .collect(),
+            ),
+        ));
+    }
+    keys
+}
+
+fn extension_default_keys(extension: &ExtensionConfig) -> Vec<(String, Value)> {
+    vec![
+        (
+            "CFBundleName".to_string(),
+            Value::String(extension.target.clone()),
+        ),
+        (
+            "CFBundleDisplayName".to_string(),
+            Value::String(extension.target.clone()),
+        ),
+        (
+            "CFBundleInfoDictionaryVersion".to_string(),
+            Value::String("6.0".to_string()),
+        ),
+        (
+            "UIDeviceFamily".to_string(),
+            Value::Array(vec![Value::Integer(1.into()), Value::Integer(2.into())]),
+        ),
+    ]
+}
+
+fn macos_version_key(platform: &PlatformConfig) -> (String, Value) {
+    (
+        "LSMinimumSystemVersion".to_string(),
+        Value::String(platform.macos_deployment_target.clone()),
+    )
+}
+
+fn strip_ios_keys(mut info: Dictionary) -> Dictionary {
+    for key in IOS_ONLY_KEYS {
+        info.remove(key);
+    }
+    info
+}
+
+fn platform_keys(platform: &PlatformConfig) -> Vec<(String, Value)> {
+    let mut keys = vec![(
+        "MinimumOSVersion".to_string(),
+        Value::String(platform.deployment_target.clone()),
+    )];
+    if !platform.device_families.is_empty() {
+        keys.push((
+            "UIDeviceFamily".to_string(),
+            Value::Array(
+                platform
+                    .device_families
+                    .iter()
+                    .map(|f| Value::Integer(f.plist_value().into()))
+                    .collect(),
+            ),
+        ));
+    }
+    if !platform.orientations.is_empty() {
+        let orientations = Value::Array(
+            platform
+                .orientations
+                .iter()
+                .map(|o| Value::String(o.plist_value().to_string()))
+                .collect(),
+        );
+        keys.push((
+            "UISupportedInterfaceOrientations".to_string(),
+            orientations.clone(),
+        ));
+        keys.push((
+            "UISupportedInterfaceOrientations~ipad".to_string(),
+            orientations,
+        ));
+    }
+    if !platform.required_capabilities.is_empty() {
+        keys.push((
+            "UIRequiredDeviceCapabilities".to_string(),
+            Value::Array(
+                platform
+                    .required_capabilities
+                    .iter()
+                    .map(|c| Value::String(c.clone()))
+                    .collect(),
+            ),
+        ));
+    }
+    keys
+}
+
+// Filled in only when the user hasn't set them
+fn default_keys(config: &ProjectConfig) -> Vec<(String, Value)> {
+    vec![
+        (
+            "CFBundleName".to_string(),
+            Value::String(config.product.clone()),
+        ),
+        (
+            "CFBundleInfoDictionaryVersion".to_string(),
+            Value::String("6.0".to_string()),
+        ),
+        (
+            "CFBundlePackageType".to_string(),
+            Value::String("APPL".to_string()),
+        ),
+        ("LSRequiresIPhoneOS".to_string(), Value::Boolean(true)),
+        (
+            "UIDeviceFamily".to_string(),
+            Value::Array(vec![Value::Integer(1.into()), Value::Integer(2.into())]),
+        ),
+    ]
+}
+
+fn find_placeholders(info: &Dictionary) -> Vec<String> {
+    let mut found = Vec::new();
+    for (key, value) in info {
+        collect_placeholders(key, value, &mut found);
+    }
+    found
+}
+
+fn collect_placeholders(path: &str, value: &Value, found: &mut Vec<String>) {
+    match value {
+        Value::String(s) => {
+            if let Some(start) = s.find("[[") {
+                if s[start..].contains("]]") {
+                    found.push(path.to_string());
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                collect_placeholders(&format!("{}[{}]", path, i), item, found);
+            }
+        }
+        Value::Dictionary(dict) => {
+            for (key, item) in dict {
+                collect_placeholders(&format!("{}.{}", path, key), item, found);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("\"{}\"", s),
+        Value::Integer(i) => i.to_string(),
+        Value::Boolean(b) => b.to_string(),
+        Value::Array(items) => format!(
+            "[{}]",
+            items.iter().map(describe).collect::<Vec<_>>().join(", ")
+        ),
+        _ => "a different value".to_string(),
+    }
+}
+
+pub fn toml_to_plist(value: &toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s.clone()),
+        toml::Value::Integer(i) => Value::Integer((*i).into()),
+        toml::Value::Float(f) => Value::Real(*f),
+        toml::Value::Boolean(b) => Value::Boolean(*b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(items) => Value::Array(items.iter().map(toml_to_plist).collect()),
+        toml::Value::Table(table) => Value::Dictionary(
+            table
+                .iter()
+                .map(|(k, v)| (k.clone(), toml_to_plist(v)))
+                .collect(),
+        ),
+    }
+}
@@ -0,0 +1,262 @@
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    process::Command,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+use tauri::{Emitter, State, Window};
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+// Printed by the WSL wrapper in SwiftBin::build_command before it execs swift
+pub const PROCESS_GROUP_PREFIX: &str = "crosscode.pgid.";
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Running,
+    Cancelling,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildJobInfo {
+    pub id: u64,
+    // Label of the window that started the job, events are sent to every window
+    pub window: String,
+    pub project: String,
+    pub kind: String,
+    pub state: JobState,
+}
+
+struct BuildJob {
+    info: BuildJobInfo,
+    // The swift process is started as the leader of its own group so the compiler and
+    // linker processes it spawns can be killed with it
+    process_group: Option<u32>,
+}
+
+#[derive(Default)]
+struct Registry {
+    next_id: u64,
+    jobs: HashMap<u64, BuildJob>,
+}
+
+#[derive(Clone, Default)]
+pub struct BuildJobs(Arc<Mutex<Registry>>);
+
+impl BuildJobs {
+    // Only one job per project may run at a time, since they would share the .build directory
+    pub fn start(
+        &self,
+        window: &Window,
+        project: &str,
+        kind: &str,
+    ) -> Result<BuildJobHandle, String> {
+        let project = normalize_project(project);
+        let mut registry = self.0.lock().unwrap();
+        if let Some(running) = registry
+            .jobs
+            .values()
+            .find(|job| job.info.project == project)
+        {
+            return Err(format!(
+                "A {} of this project is already running (job {})",
+                running.info.kind, running.info.id
+            ));
+        }
+
+        registry.next_id += 1;
+        let info = BuildJobInfo {
+            id: registry.next_id,
+            window: window.label().to_string(),
+            project,
+            kind: kind.to_string(),
+            state: JobState::Running,
+        };
+        window.emit("build-job", &info).ok();
+        registry.jobs.insert(
+            info.id,
+            BuildJob {
+                info: info.clone(),
+                process_group: None,
+            },
+        );
+
+        Ok(BuildJobHandle {
+            process: JobProcess {
+                jobs: self.clone(),
+                id: info.id,
+            },
+            window: window.clone(),
+            succeeded: false,
+        })
+    }
+
+    pub fn list(&self) -> Vec<BuildJobInfo> {
+        let registry = self.0.lock().unwrap();
+        let mut jobs: Vec<BuildJobInfo> = registry.jobs.values().map(|j| j.info.clone()).collect();
+        jobs.sort_by_key(|j| j.id);
+        jobs
+    }
+
+    // Cancels one of the window's jobs, or all of them when no ID is given. Jobs started
+    // by other windows are left alone.
+    pub fn cancel(&self, window: &Window, id: Option<u64>) -> Result<Vec<u64>, String> {
+        let mut registry = self.0.lock().unwrap();
+        if let Some(id) = id {
+            if registry
+                .jobs
+                .get(&id)
+                .is_none_or(|job| job.info.window != window.label())
+            {
+                return Err(format!("No running build job with ID {}", id));
+            }
+        }
+
+        let mut cancelled = Vec::new();
+        for job in registry.jobs.values_mut() {
+            if id.is_some_and(|id| id != job.info.id)
+                || job.info.window != window.label()
+                || job.info.state != JobState::Running
+            {
+                continue;
+            }
+            job.info.state = JobState::Cancelling;
+            window.emit("build-job", &job.info).ok();
+            if let Some(process_group) = job.process_group {
+                kill_process_group(process_group);
+            }
+            cancelled.push(job.info.id);
+        }
+        Ok(cancelled)
+    }
+}
+
+// What the output readers need to attach the swift process to its job
+#[derive(Clone)]
+pub struct JobProcess {
+    jobs: BuildJobs,
+    id: u64,
+}
+
+impl JobProcess {
+    pub fn set_process_group(&self, process_group: u32) {
+        let mut registry = self.jobs.0.lock().unwrap();
+        if let Some(job) = registry.jobs.get_mut(&self.id) {
+            job.process_group = Some(process_group);
+            // Cancelled before the process existed
+            if job.info.state == JobState::Cancelling {
+                kill_process_group(process_group);
+            }
+        }
+    }
+
+    pub fn clear_process_group(&self) {
+        let mut registry = self.jobs.0.lock().unwrap();
+        if let Some(job) = registry.jobs.get_mut(&self.id) {
+            job.process_group = None;
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        let registry = self.jobs.0.lock().unwrap();
+        registry
+            .jobs
+            .get(&self.id)
+            .is_some_and(|job| job.info.state == JobState::Cancelling)
+    }
+
+    pub fn check_cancelled(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            return Err("Build cancelled".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Removes the job from the registry and reports how it ended once dropped
+pub struct BuildJobHandle {
+    process: JobProcess,
+    window: Window,
+    succeeded: bool,
+}
+
+impl BuildJobHandle {
+    pub fn process(&self) -> &JobProcess {
+        &self.process
+    }
+
+    pub fn check_cancelled(&self) -> Result<(), String> {
+        self.process.check_cancelled()
+    }
+
+    pub fn succeed(mut self) {
+        self.succeeded = true;
+    }
+}
+
+impl Drop for BuildJobHandle {
+    fn drop(&mut self) {
+        let mut registry = self.process.jobs.0.lock().unwrap();
+        if let Some(mut job) = registry.jobs.remove(&self.process.id) {
+            // A cancel that arrived too late to stop anything doesn't turn a success into one
+            job.info.state = if self.succeeded {
+                JobState::Succeeded
+            } else if job.info.state == JobState::Cancelling {
+                JobState::Cancelled
+            } else {
+                JobState::Failed
+            };
+            self.window.emit("build-job", &job.info).ok();
+        }
+    }
+}
+
+fn normalize_project(project: &str) -> String {
+    PathBuf::from(project)
+        .canonicalize()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or(project.to_string())
+}
+
+fn kill_process_group(process_group: u32) {
+    let target = format!("-{}", process_group);
+    #[cfg(target_os = "windows")]
+    {
+        Command::new("wsl")
+            .args(["--exec", "kill", "-TERM", "--", &target])
+            .creation_flags(CREATE_NO_WINDOW)
+            .status()
+            .ok();
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        Command::new("kill")
+            .args(["-TERM", "--", &target])
+            .status()
+            .ok();
+    }
+}
+
+#[tauri::command]
+pub fn cancel_build(
+    window: Window,
+    jobs: State<'_, BuildJobs>,
+    job_id: Option<u64>,
+) -> Result<Vec<u64>, String> {
+    jobs.cancel(&window, job_id)
+}
+
+#[tauri::command]
+pub fn list_build_jobs(jobs: State<'_, BuildJobs>) -> Vec<BuildJobInfo> {
+    jobs.list()
+}
@@ -0,0 +1,640 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use plist::{Dictionary, Value};
+use serde::Serialize;
+
+use crate::builder::swift::SwiftBin;
+
+const MH_MAGIC: u32 = 0xfeedface;
+const MH_MAGIC_64: u32 = 0xfeedfacf;
+const FAT_MAGIC: u32 = 0xcafebabe;
+const FAT_MAGIC_64: u32 = 0xcafebabf;
+
+const CPU_ARCH_ABI64: u32 = 0x01000000;
+const CPU_TYPE_X86: u32 = 7;
+const CPU_TYPE_ARM: u32 = 12;
+const CPU_TYPE_X86_64: u32 = CPU_TYPE_X86 | CPU_ARCH_ABI64;
+const CPU_TYPE_ARM64: u32 = CPU_TYPE_ARM | CPU_ARCH_ABI64;
+const CPU_SUBTYPE_MASK: u32 = 0x00ffffff;
+const CPU_SUBTYPE_ARM64E: u32 = 2;
+
+pub const MH_EXECUTE: u32 = 0x2;
+pub const MH_DYLIB: u32 = 0x6;
+
+const LC_REQ_DYLD: u32 = 0x80000000;
+const LC_SEGMENT: u32 = 0x1;
+const LC_SYMTAB: u32 = 0x2;
+const LC_SEGMENT_64: u32 = 0x19;
+const LC_LOAD_DYLIB: u32 = 0xc;
+const LC_LOAD_WEAK_DYLIB: u32 = 0x18 | LC_REQ_DYLD;
+const LC_UUID: u32 = 0x1b;
+const LC_CODE_SIGNATURE: u32 = 0x1d;
+const LC_RPATH: u32 = 0x1c | LC_REQ_DYLD;
+const LC_REEXPORT_DYLIB: u32 = 0x1f | LC_REQ_DYLD;
+const LC_LAZY_LOAD_DYLIB: u32 = 0x20;
+const LC_LOAD_UPWARD_DYLIB: u32 = 0x23 | LC_REQ_DYLD;
+const LC_VERSION_MIN_MACOSX: u32 = 0x24;
+const LC_VERSION_MIN_IPHONEOS: u32 = 0x25;
+const LC_BUILD_VERSION: u32 = 0x32;
+
+const CSMAGIC_CODEDIRECTORY: u32 = 0xfade0c02;
+const CSMAGIC_EMBEDDED_SIGNATURE: u32 = 0xfade0cc0;
+const CSMAGIC_EMBEDDED_ENTITLEMENTS: u32 = 0xfade7171;
+const CSSLOT_CODEDIRECTORY: u32 = 0;
+const CSSLOT_ENTITLEMENTS: u32 = 5;
+const CS_ADHOC: u32 = 0x2;
+const CS_SUPPORTSTEAMID: u32 = 0x20200;
+
+const N_STAB: u8 = 0xe0;
+const N_TYPE: u8 = 0x0e;
+const N_SECT: u8 = 0x0e;
+
+// One architecture slice, only the load commands CrossCode cares about
+pub struct MachO {
+    pub cpu_type: u32,
+    pub cpu_subtype: u32,
+    pub file_type: u32,
+    pub load_commands: Vec<LoadCommand>,
+    pub segments: Vec<Segment>,
+    pub build_version: Option<BuildVersion>,
+    pub dylibs: Vec<String>,
+    pub rpaths: Vec<String>,
+    // Formatted the way crash reports and dSYMs show it
+    pub uuid: Option<String>,
+    // Unslid load address, crash reports give addresses as offsets from it
+    pub text_vmaddr: u64,
+    // Defined symbols sorted by address
+    pub symbols: Vec<Symbol>,
+    pub code_signature: Option<CodeSignature>,
+    // From the code signature, if the binary was signed with any
+    pub entitlements: Option<Dictionary>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadCommand {
+    pub name: String,
+    pub size: u32,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Segment {
+    pub name: String,
+    pub vm_address: u64,
+    pub vm_size: u64,
+    pub file_offset: u64,
+    pub file_size: u64,
+}
+
+// From LC_BUILD_VERSION, or the LC_VERSION_MIN_* commands older linkers emit
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildVersion {
+    pub platform: String,
+    pub min_os: String,
+    pub sdk: String,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeSignature {
+    pub size: u32,
+    pub identifier: Option<String>,
+    // Ad-hoc signatures have no certificate and no team
+    pub team_id: Option<String>,
+    pub adhoc: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MachOReport {
+    pub arch: String,
+    pub file_type: String,
+    pub uuid: Option<String>,
+    pub build_version: Option<BuildVersion>,
+    pub load_commands: Vec<LoadCommand>,
+    pub segments: Vec<Segment>,
+    pub dylibs: Vec<LinkedDylib>,
+    pub rpaths: Vec<String>,
+    pub code_signature: Option<CodeSignature>,
+    pub entitlements: Option<Dictionary>,
+    pub symbol_count: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkedDylib {
+    pub path: String,
+    // Only known for system libraries, and only when the Darwin SDK is installed
+    pub in_sdk: Option<bool>,
+}
+
+pub struct Symbol {
+    pub address: u64,
+    pub name: String,
+}
+
+impl MachO {
+    pub fn arch(&self) -> &'static str {
+        match (self.cpu_type, self.cpu_subtype & CPU_SUBTYPE_MASK) {
+            (CPU_TYPE_ARM64, CPU_SUBTYPE_ARM64E) => "arm64e",
+            (CPU_TYPE_ARM64, _) => "arm64",
+            (CPU_TYPE_X86_64, _) => "x86_64",
+            (CPU_TYPE_ARM, _) => "armv7",
+            (CPU_TYPE_X86, _) => "i386",
+            _ => "unknown",
+        }
+    }
+
+    // The Darwin SDK triple the slice was built for
+    fn sdk_triple(&self) -> Option<String> {
+        let platform = match self.build_version.as_ref()?.platform.as_str() {
+            platform @ ("ios" | "ios-simulator" | "macos") => platform,
+            _ => return None,
+        };
+        let arch = match self.arch() {
+            "arm64" | "arm64e" => "arm64",
+            "x86_64" => "x86_64",
+            _ => return None,
+        };
+        Some(format!("{}-apple-{}", arch, platform))
+    }
+
+    pub fn file_type_name(&self) -> &'static str {
+        match self.file_type {
+            0x1 => "object",
+            MH_EXECUTE => "executable",
+            MH_DYLIB => "dylib",
+            0x8 => "bundle",
+            0xa => "dSYM",
+            _ => "other",
+        }
+    }
+
+    // The symbol whose code contains the address and the offset into it
+    pub fn lookup(&self, address: u64) -> Option<(&Symbol, u64)> {
+        let index = self.symbols.partition_point(|s| s.address <= address);
+        let symbol = self.symbols.get(index.checked_sub(1)?)?;
+        Some((symbol, address - symbol.address))
+    }
+}
+
+pub fn read_macho(path: &Path) -> Result<Vec<MachO>, String> {
+    let data = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_macho(&data).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+// Only reads the magic, cheap enough to run on every file in a bundle
+pub fn is_macho(path: &Path) -> bool {
+    let mut magic = [0u8; 4];
+    if File::open(path)
+        .and_then(|mut f| f.read_exact(&mut magic))
+        .is_err()
+    {
+        return false;
+    }
+    matches!(u32::from_le_bytes(magic), MH_MAGIC | MH_MAGIC_64)
+        || matches!(u32::from_be_bytes(magic), FAT_MAGIC | FAT_MAGIC_64)
+}
+
+fn parse_macho(data: &[u8]) -> Result<Vec<MachO>, String> {
+    let magic = read_u32_be(data, 0)?;
+    if magic == FAT_MAGIC || magic == FAT_MAGIC_64 {
+        let is_64 = magic == FAT_MAGIC_64;
+        let count = read_u32_be(data, 4)? as usize;
+        let mut slices = Vec::with_capacity(count);
+        for i in 0..count {
+            let (offset, size) = if is_64 {
+                let arch = 8 + i * 32;
+                (read_u64_be(data, arch + 8)?, read_u64_be(data, arch + 16)?)
+            } else {
+                let arch = 8 + i * 20;
+                (
+                    read_u32_be(data, arch + 8)? as u64,
+                    read_u32_be(data, arch + 12)? as u64,
+                )
+            };
+            let slice = offset
+                .checked_add(size)
+                .and_then(|end| data.get(offset as usize..end as usize))
+                .ok_or("Fat architecture extends past end of file".to_string())?;
+            slices.push(parse_thin(slice)?);
+        }
+        Ok(slices)
+    } else {
+        Ok(vec![parse_thin(data)?])
+    }
+}
+
+fn parse_thin(data: &[u8]) -> Result<MachO, String> {
+    let (header_size, is_64) = match read_u32_le(data, 0)? {
+        MH_MAGIC => (28, false),
+        MH_MAGIC_64 => (32, true),
+        _ => return Err("Not a Mach-O file".to_string()),
+    };
+    let mut macho = MachO {
+        cpu_type: read_u32_le(data, 4)?,
+        cpu_subtype: read_u32_le(data, 8)?,
+        file_type: read_u32_le(data, 12)?,
+        load_commands: Vec::new(),
+        segments: Vec::new(),
+        build_version: None,
+        dylibs: Vec::new(),
+        rpaths: Vec::new(),
+        uuid: None,
+        text_vmaddr: 0,
+        symbols: Vec::new(),
+        code_signature: None,
+        entitlements: None,
+    };
+    let ncmds = read_u32_le(data, 16)?;
+
+    let mut offset = header_size;
+    for _ in 0..ncmds {
+        let cmd = read_u32_le(data, offset)?;
+        let cmd_size = read_u32_le(data, offset + 4)? as usize;
+        if cmd_size < 8 {
+            return Err("Malformed load command".to_string());
+        }
+        let command = data
+            .get(offset..offset + cmd_size)
+            .ok_or("Load command extends past end of file".to_string())?;
+        macho.load_commands.push(LoadCommand {
+            name: load_command_name(cmd),
+            size: cmd_size as u32,
+        });
+        match cmd {
+            LC_LOAD_DYLIB | LC_LOAD_WEAK_DYLIB | LC_REEXPORT_DYLIB | LC_LAZY_LOAD_DYLIB
+            | LC_LOAD_UPWARD_DYLIB => macho.dylibs.push(read_lc_str(command)?),
+            LC_RPATH => macho.rpaths.push(read_lc_str(command)?),
+            LC_UUID => macho.uuid = Some(format_uuid(command)?),
+            LC_SEGMENT | LC_SEGMENT_64 => {
+                let segment = read_segment(command, cmd == LC_SEGMENT_64)?;
+                if segment.name == "__TEXT" {
+                    macho.text_vmaddr = segment.vm_address;
+                }
+                macho.segments.push(segment);
+            }
+            LC_BUILD_VERSION => {
+                macho.build_version = Some(BuildVersion {
+                    platform: platform_name(read_u32_le(command, 8)?),
+                    min_os: format_version(read_u32_le(command, 12)?),
+                    sdk: format_version(read_u32_le(command, 16)?),
+                })
+            }
+            LC_VERSION_MIN_MACOSX | LC_VERSION_MIN_IPHONEOS if macho.build_version.is_none() => {
+                macho.build_version = Some(BuildVersion {
+                    platform: if cmd == LC_VERSION_MIN_MACOSX {
+                        "macos"
+                    } else {
+                        "ios"
+                    }
+                    .to_string(),
+                    min_os: format_version(read_u32_le(command, 8)?),
+                    sdk: format_version(read_u32_le(command, 12)?),
+                })
+            }
+            LC_SYMTAB => {
+                macho.symbols = read_symbols(
+                    data,
+                    read_u32_le(command, 8)? as usize,
+                    read_u32_le(command, 12)? as usize,
+                    read_u32_le(command, 16)? as usize,
+                    is_64,
+                )?
+            }
+            LC_CODE_SIGNATURE => {
+                let start = read_u32_le(command, 8)? as usize;
+                let size = read_u32_le(command, 12)? as usize;
+                let signature = data
+                    .get(start..start + size)
+                    .ok_or("Code signature out of bounds".to_string())?;
+                macho.entitlements = read_entitlements(signature)?;
+                macho.code_signature = Some(read_code_directory(signature, size as u32)?);
+            }
+            _ => {}
+        }
+        offset += cmd_size;
+    }
+
+    macho.symbols.sort_by_key(|s| s.address);
+    Ok(macho)
+}
+
+// Strings in load commands are stored as an offset from the start of the command
+fn read_lc_str(command: &[u8]) -> Result<String, String> {
+    let start = read_u32_le(command, 8)? as usize;
+    let bytes = command
+        .get(start..)
+        .ok_or("Load command string out of bounds".to_string())?;
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    Ok(String::from_utf8_lossy(&bytes[..end]).to_string())
+}
+
+fn read_symbols(
+    data: &[u8],
+    symoff: usize,
+    nsyms: usize,
+    stroff: usize,
+    is_64: bool,
+) -> Result<Vec<Symbol>, String> {
+    let entry_size = if is_64 { 16 } else { 12 };
+    let strings = data
+        .get(stroff..)
+        .ok_or("String table out of bounds".to_string())?;
+    let mut symbols = Vec::new();
+    for i in 0..nsyms {
+        let entry = symoff + i * entry_size;
+        let n_type = *data
+            .get(entry + 4)
+            .ok_or("Symbol table out of bounds".to_string())?;
+        // Only symbols defined in a section, debugger entries and imports are skipped
+        if n_type & N_STAB != 0 || n_type & N_TYPE != N_SECT {
+            continue;
+        }
+        let address = if is_64 {
+            read_u64_le(data, entry + 8)?
+        } else {
+            read_u32_le(data, entry + 8)? as u64
+        };
+        let name_start = read_u32_le(data, entry)? as usize;
+        let name = strings
+            .get(name_start..)
+            .map(|bytes| {
+                let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+                String::from_utf8_lossy(&bytes[..end]).to_string()
+            })
+            .unwrap_or_default();
+        if !name.is_empty() {
+            symbols.push(Symbol { address, name });
+        }
+    }
+    Ok(symbols)
+}
+
+// The signature is a big-endian blob with an index of sub-blobs, entitlements are kept in
+// one of them as an XML plist
+fn read_entitlements(signature: &[u8]) -> Result<Option<Dictionary>, String> {
+    let blob_start = match find_blob(signature, CSSLOT_ENTITLEMENTS)? {
+        Some(start) => start,
+        None => return Ok(None),
+    };
+    if read_u32_be(signature, blob_start)? != CSMAGIC_EMBEDDED_ENTITLEMENTS {
+        return Ok(None);
+    }
+    let length = read_u32_be(signature, blob_start + 4)? as usize;
+    let xml = signature
+        .get(blob_start + 8..blob_start + length)
+        .ok_or("Entitlements blob out of bounds".to_string())?;
+    let value =
+        Value::from_reader_xml(xml).map_err(|e| format!("Failed to parse entitlements: {}", e))?;
+    Ok(value.into_dictionary())
+}
+
+// The code directory holds the signing identifier and, for signatures made with a
+// certificate, the team
+fn read_code_directory(signature: &[u8], size: u32) -> Result<CodeSignature, String> {
+    let mut code_signature = CodeSignature {
+        size,
+        identifier: None,
+        team_id: None,
+        adhoc: false,
+    };
+    let start = match find_blob(signature, CSSLOT_CODEDIRECTORY)? {
+        Some(start) => start,
+        None => return Ok(code_signature),
+    };
+    if read_u32_be(signature, start)? != CSMAGIC_CODEDIRECTORY {
+        return Ok(code_signature);
+    }
+    let version = read_u32_be(signature, start + 8)?;
+    code_signature.adhoc = read_u32_be(signature, start + 12)? & CS_ADHOC != 0;
+    let ident_offset = read_u32_be(signature, start + 20)? as usize;
+    code_signature.identifier = read_c_str(signature, start + ident_offset);
+    if version >= CS_SUPPORTSTEAMID {
+        let team_offset = read_u32_be(signature, start + 48)? as usize;
+        if team_offset != 0 {
+            code_signature.team_id = read_c_str(signature, start + team_offset);
+        }
+    }
+    Ok(code_signature)
+}
+
+fn find_blob(signature: &[u8], slot_type: u32) -> Result<Option<usize>, String> {
+    if read_u32_be(signature, 0)? != CSMAGIC_EMBEDDED_SIGNATURE {
+        return Ok(None);
+    }
+    let count = read_u32_be(signature, 8)? as usize;
+    for i in 0..count {
+        let slot = 12 + i * 8;
+        if read_u32_be(signature, slot)? == slot_type {
+            return Ok(Some(read_u32_be(signature, slot + 4)? as usize));
+        }
+    }
+    Ok(None)
+}
+
+fn read_c_str(data: &[u8], offset: usize) -> Option<String> {
+    let bytes = data.get(offset..)?;
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    Some(String::from_utf8_lossy(&bytes[..end]).to_string())
+}
+
+fn read_segment(command: &[u8], is_64: bool) -> Result<Segment, String> {
+    let name = read_fixed_str(command, 8, 16)?;
+    Ok(if is_64 {
+        Segment {
+            name,
+            vm_address: read_u64_le(command, 24)?,
+            vm_size: read_u64_le(command, 32)?,
+            file_offset: read_u64_le(command, 40)?,
+            file_size: read_u64_le(command, 48)?,
+        }
+    } else {
+        Segment {
+            name,
+            vm_address: read_u32_le(command, 24)? as u64,
+            vm_size: read_u32_le(command, 28)? as u64,
+            file_offset: read_u32_le(command, 32)? as u64,
+            file_size: read_u32_le(command, 36)? as u64,
+        }
+    })
+}
+
+// Versions are packed as xxxx.yy.zz
+fn format_version(version: u32) -> String {
+    let (major, minor, patch) = (version >> 16, (version >> 8) & 0xff, version & 0xff);
+    if patch == 0 {
+        format!("{}.{}", major, minor)
+    } else {
+        format!("{}.{}.{}", major, minor, patch)
+    }
+}
+
+fn platform_name(platform: u32) -> String {
+    match platform {
+        1 => "macos",
+        2 => "ios",
+        3 => "tvos",
+        4 => "watchos",
+        6 => "maccatalyst",
+        7 => "ios-simulator",
+        8 => "tvos-simulator",
+        9 => "watchos-simulator",
+        11 => "visionos",
+        12 => "visionos-simulator",
+        _ => return format!("unknown ({})", platform),
+    }
+    .to_string()
+}
+
+fn load_command_name(cmd: u32) -> String {
+    match cmd {
+        LC_SEGMENT => "LC_SEGMENT",
+        LC_SYMTAB => "LC_SYMTAB",
+        0x4 => "LC_THREAD",
+        0x5 => "LC_UNIXTHREAD",
+        0xb => "LC_DYSYMTAB",
+        LC_LOAD_DYLIB => "LC_LOAD_DYLIB",
+        0xd => "LC_ID_DYLIB",
+        0xe => "LC_LOAD_DYLINKER",
+        0xf => "LC_ID_DYLINKER",
+        LC_LOAD_WEAK_DYLIB => "LC_LOAD_WEAK_DYLIB",
+        LC_SEGMENT_64 => "LC_SEGMENT_64",
+        LC_UUID => "LC_UUID",
+        LC_RPATH => "LC_RPATH",
+        LC_CODE_SIGNATURE => "LC_CODE_SIGNATURE",
+        0x1e => "LC_SEGMENT_SPLIT_INFO",
+        LC_REEXPORT_DYLIB => "LC_REEXPORT_DYLIB",
+        LC_LAZY_LOAD_DYLIB => "LC_LAZY_LOAD_DYLIB",
+        0x21 => "LC_ENCRYPTION_INFO",
+        0x22 => "LC_DYLD_INFO",
+        0x80000022 => "LC_DYLD_INFO_ONLY",
+        LC_LOAD_UPWARD_DYLIB => "LC_LOAD_UPWARD_DYLIB",
+        LC_VERSION_MIN_MACOSX => "LC_VERSION_MIN_MACOSX",
+        LC_VERSION_MIN_IPHONEOS => "LC_VERSION_MIN_IPHONEOS",
+        0x26 => "LC_FUNCTION_STARTS",
+        0x27 => "LC_DYLD_ENVIRONMENT",
+        0x80000028 => "LC_MAIN",
+        0x29 => "LC_DATA_IN_CODE",
+        0x2a => "LC_SOURCE_VERSION",
+        0x2b => "LC_DYLIB_CODE_SIGN_DRS",
+        0x2c => "LC_ENCRYPTION_INFO_64",
+        0x2d => "LC_LINKER_OPTION",
+        0x2e => "LC_LINKER_OPTIMIZATION_HINT",
+        LC_BUILD_VERSION => "LC_BUILD_VERSION",
+        0x80000033 => "LC_DYLD_EXPORTS_TRIE",
+        0x80000034 => "LC_DYLD_CHAINED_FIXUPS",
+        _ => return format!("0x{:x}", cmd),
+    }
+    .to_string()
+}
+
+// Segment names are padded with zeros to a fixed length
+fn read_fixed_str(command: &[u8], offset: usize, len: usize) -> Result<String, String> {
+    let bytes = command
+        .get(offset..offset + len)
+        .ok_or("Load command too short".to_string())?;
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(len);
+    Ok(String::from_utf8_lossy(&bytes[..end]).to_string())
+}
+
+fn format_uuid(command: &[u8]) -> Result<String, String> {
+    let bytes = command
+        .get(8..24)
+        .ok_or("UUID load command too short".to_string())?;
+    let hex: String = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+    Ok(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    ))
+}
+
+fn read_u32_le(data: &[u8], offset: usize) -> Result<u32, String> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .ok_or("Unexpected end of file".to_string())
+}
+
+fn read_u64_le(data: &[u8], offset: usize) -> Result<u64, String> {
+    data.get(offset..offset + 8)
+        .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+        .ok_or("Unexpected end of file".to_string())
+}
+
+fn read_u32_be(data: &[u8], offset: usize) -> Result<u32, String> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_be_bytes(b.try_into().unwrap()))
+        .ok_or("Unexpected end of file".to_string())
+}
+
+fn read_u64_be(data: &[u8], offset: usize) -> Result<u64, String> {
+    data.get(offset..offset + 8)
+        .map(|b| u64::from_be_bytes(b.try_into().unwrap()))
+        .ok_or("Unexpected end of file".to_string())
+}
+
+// The SDK only ships .tbd stubs in place of the real libraries
+fn in_sdk(sdk_root: &Path, dylib: &str) -> Option<bool> {
+    if !dylib.starts_with('/') {
+        return None;
+    }
+    let path = sdk_root.join(dylib.trim_start_matches('/'));
+    Some(path.exists() || path.with_extension("tbd").exists())
+}
+
+#[tauri::command]
+pub async fn inspect_macho(
+    path: String,
+    toolchain_path: Option<String>,
+) -> Result<Vec<MachOReport>, String> {
+    let slices = read_macho(Path::new(&path))?;
+    let swift_bin = match toolchain_path.filter(|p| !p.is_empty()) {
+        Some(toolchain_path) => Some(SwiftBin::new(&toolchain_path)?),
+        None => None,
+    };
+
+    let mut sdk_roots: HashMap<String, Option<PathBuf>> = HashMap::new();
+    let mut reports = Vec::new();
+    for slice in slices {
+        let sdk_root = match (&swift_bin, slice.sdk_triple()) {
+            (Some(swift_bin), Some(triple)) => sdk_roots
+                .entry(triple.clone())
+                .or_insert_with(|| swift_bin.sdk_root(&triple).ok())
+                .clone(),
+            _ => None,
+        };
+        let dylibs = slice
+            .dylibs
+            .iter()
+            .map(|dylib| LinkedDylib {
+                path: dylib.clone(),
+                in_sdk: sdk_root.as_ref().and_then(|root| in_sdk(root, dylib)),
+            })
+            .collect();
+        reports.push(MachOReport {
+            arch: slice.arch().to_string(),
+            file_type: slice.file_type_name().to_string(),
+            uuid: slice.uuid,
+            build_version: slice.build_version,
+            load_commands: slice.load_commands,
+            segments: slice.segments,
+            dylibs,
+            rpaths: slice.rpaths,
+            code_signature: slice.code_signature,
+            entitlements: slice.entitlements,
+            symbol_count: slice.symbols.len(),
+        });
+    }
+    Ok(reports)
+}
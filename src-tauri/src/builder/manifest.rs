@@ -0,0 +1,257 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use regex::Regex;
+
+use crate::builder::{package::SwiftPackageDump, swift::SwiftBin};
+
+// Edits to Package.swift are made on the source text, so everything the user wrote stays as
+// it was. These scan the top level Package(...) call without running Swift.
+
+// The manifest with comments and string contents blanked out, byte for byte, so the call
+// structure can be scanned without being fooled by brackets inside them
+pub fn code_mask(source: &str) -> Vec<u8> {
+    let bytes = source.as_bytes();
+    let mut mask = bytes.to_vec();
+    let blank = |mask: &mut Vec<u8>, from: usize, to: usize| {
+        for byte in &mut mask[from..to.min(bytes.len())] {
+            if *byte != b'\n' {
+                *byte = b' ';
+            }
+        }
+    };
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        if rest.starts_with(b"//") {
+            let end = rest
+                .iter()
+                .position(|b| *b == b'\n')
+                .map_or(bytes.len(), |p| i + p);
+            blank(&mut mask, i, end);
+            i = end;
+        } else if rest.starts_with(b"/*") {
+            // Block comments nest in Swift
+            let mut depth = 0;
+            let mut j = i;
+            while j < bytes.len() {
+                if bytes[j..].starts_with(b"/*") {
+                    depth += 1;
+                    j += 2;
+                } else if bytes[j..].starts_with(b"*/") {
+                    depth -= 1;
+                    j += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    j += 1;
+                }
+            }
+            blank(&mut mask, i, j);
+            i = j;
+        } else if rest[0] == b'"' || rest[0] == b'#' {
+            let hashes = rest.iter().take_while(|b| **b == b'#').count();
+            if rest.get(hashes) != Some(&b'"') {
+                i += hashes.max(1);
+                continue;
+            }
+            let quotes = if rest[hashes..].starts_with(b"\"\"\"") {
+                3
+            } else {
+                1
+            };
+            let mut close = vec![b'"'; quotes];
+            close.extend(std::iter::repeat_n(b'#', hashes));
+            let mut j = i + hashes + quotes;
+            while j < bytes.len() && !bytes[j..].starts_with(&close) {
+                // Escapes in raw strings need as many hashes as the delimiter
+                j += if bytes[j] == b'\\' && hashes == 0 {
+                    2
+                } else {
+                    1
+                };
+            }
+            let end = (j + close.len()).min(bytes.len());
+            // Keep the delimiters so string literals still read as values
+            blank(&mut mask, i + hashes + quotes, j);
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    mask
+}
+
+// The index of the bracket closing the one at open
+pub fn matching_close(mask: &[u8], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, byte) in mask.iter().enumerate().skip(open) {
+        match byte {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// Top level comma separated items between from and to, trimmed, as byte ranges
+pub fn split_items(mask: &[u8], from: usize, to: usize) -> Vec<(usize, usize)> {
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut start = from;
+    for i in from..=to {
+        let byte = if i == to { b',' } else { mask[i] };
+        match byte {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth -= 1,
+            b',' if depth == 0 => {
+                let mut s = start;
+                let mut e = i;
+                while s < e && mask[s].is_ascii_whitespace() {
+                    s += 1;
+                }
+                while e > s && mask[e - 1].is_ascii_whitespace() {
+                    e -= 1;
+                }
+                if s < e {
+                    items.push((s, e));
+                }
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items
+}
+
+pub struct ManifestLayout {
+    // The arguments of the Package(...) call
+    pub arguments: Vec<(usize, usize)>,
+    pub package_start: usize,
+}
+
+impl ManifestLayout {
+    // The top level argument with the label, never one nested in a target
+    pub fn argument(&self, source: &str, name: &str) -> Option<(usize, usize)> {
+        self.arguments
+            .iter()
+            .find(|(s, e)| label(source, *s, *e) == Some(name))
+            .copied()
+    }
+
+    // The brackets of an array literal argument like platforms: [...]
+    pub fn array_argument(
+        &self,
+        source: &str,
+        mask: &[u8],
+        name: &str,
+    ) -> Result<Option<(usize, usize)>, String> {
+        let Some((start, end)) = self.argument(source, name) else {
+            return Ok(None);
+        };
+        let open = start
+            + mask[start..end]
+                .iter()
+                .position(|b| *b == b'[')
+                .ok_or(format!("{} must be an array literal to be edited", name))?;
+        let close = matching_close(mask, open)
+            .ok_or(format!("Package.swift has an unclosed {} array", name))?;
+        Ok(Some((open, close)))
+    }
+}
+
+pub fn manifest_layout(mask: &[u8]) -> Result<ManifestLayout, String> {
+    let masked = String::from_utf8_lossy(mask);
+    let call = Regex::new(r"\bPackage\s*\(").unwrap();
+    let found = call
+        .find(&masked)
+        .ok_or("Package.swift has no Package(...) call".to_string())?;
+    let open = found.end() - 1;
+    let close = matching_close(mask, open)
+        .ok_or("Package.swift has an unclosed Package(...) call".to_string())?;
+    let arguments = split_items(mask, open + 1, close);
+    Ok(ManifestLayout {
+        arguments,
+        package_start: found.start(),
+    })
+}
+
+fn label(source: &str, start: usize, end: usize) -> Option<&str> {
+    let (label, _) = source[start..end].split_once(':')?;
+    let label = label.trim();
+    label
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '_')
+        .then_some(label)
+}
+
+pub fn line_start(source: &str, at: usize) -> usize {
+    source[..at].rfind('\n').map_or(0, |i| i + 1)
+}
+
+pub fn line_end(source: &str, at: usize) -> usize {
+    source[at..].find('\n').map_or(source.len(), |i| at + i)
+}
+
+pub fn indentation(source: &str, at: usize) -> &str {
+    let start = line_start(source, at);
+    let line = &source[start..];
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+// Whether only whitespace comes between the start of the line and at
+pub fn starts_line(source: &str, at: usize) -> bool {
+    source[line_start(source, at)..at].trim().is_empty()
+}
+
+// One level deeper than indent, using the step the Package(...) arguments are indented by
+pub fn nested_indentation(source: &str, indent: &str, package_start: usize) -> String {
+    let package_indent = indentation(source, package_start);
+    match indent.strip_prefix(package_indent) {
+        Some(step) if !step.is_empty() => step.to_string(),
+        _ => "    ".to_string(),
+    }
+}
+
+// Just past the comma following an entry that ends at end, if it has one
+pub fn comma_after(mask: &[u8], end: usize, close: usize) -> Option<usize> {
+    let p = mask[end..close]
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())?;
+    (mask[end + p] == b',').then_some(end + p + 1)
+}
+
+pub fn manifest_path(project_path: &Path) -> PathBuf {
+    project_path.join("Package.swift")
+}
+
+// Writes the edited manifest, and puts the original back if SwiftPM can't load it or the
+// edit didn't do what it should have
+pub fn write_manifest(
+    swift: &SwiftBin,
+    project_path: &Path,
+    original: &str,
+    edited: &str,
+    check: impl Fn(&SwiftPackageDump) -> Result<(), String>,
+) -> Result<SwiftPackageDump, String> {
+    let path = manifest_path(project_path);
+    fs::write(&path, edited).map_err(|e| format!("Failed to write Package.swift: {}", e))?;
+    let result = SwiftPackageDump::load(swift, project_path).and_then(|package| {
+        check(&package)?;
+        Ok(package)
+    });
+    if result.is_err() {
+        fs::write(&path, original)
+            .map_err(|e| format!("Failed to restore Package.swift: {}", e))?;
+    }
+    result
+}
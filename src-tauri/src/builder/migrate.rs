@@ -0,0 +1,238 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use toml_edit::DocumentMut;
+
+use crate::builder::config::{TomlConfig, FORMAT_VERSION};
+
+// Each step upgrades a crosscode.toml from format version N to N + 1. They edit the document
+// in place, so comments and key order survive.
+pub type Migration = fn(&mut DocumentMut) -> Result<(), String>;
+
+// MIGRATIONS[0] upgrades version 1 to 2 and so on. Empty until the layout first changes.
+pub const MIGRATIONS: &[Migration] = &[];
+
+const _: () = assert!(MIGRATIONS.len() + 1 == FORMAT_VERSION as usize);
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigMigration {
+    pub from_version: u32,
+    pub to_version: u32,
+    // Line diff of crosscode.toml, prefixed with "+", "-" or " "
+    pub diff: String,
+    // Where the original file was copied to, None for a dry run
+    pub backup_path: Option<String>,
+}
+
+pub fn read_format_version(content: &str) -> Result<u32, String> {
+    let table = content
+        .parse::<toml::Table>()
+        .map_err(|e| format!("Failed to parse crosscode.toml: {}", e))?;
+    table
+        .get("format_version")
+        .and_then(|v| v.as_integer())
+        .and_then(|v| u32::try_from(v).ok())
+        .filter(|v| *v > 0)
+        .ok_or("crosscode.toml has no valid format_version".to_string())
+}
+
+// Upgrades crosscode.toml through the given steps, normally MIGRATIONS
+pub fn migrate_project_config(
+    project_path: &Path,
+    migrations: &[Migration],
+    dry_run: bool,
+) -> Result<ConfigMigration, String> {
+    let to_version = migrations.len() as u32 + 1;
+    let path = project_path.join("crosscode.toml");
+    let original = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let from_version = read_format_version(&original)?;
+    if from_version > to_version {
+        return Err(format!(
+            "Unsupported format version: {}, this version of CrossCode only supports up to {}",
+            from_version, to_version
+        ));
+    }
+
+    let mut document = original
+        .parse::<DocumentMut>()
+        .map_err(|e| format!("Failed to parse crosscode.toml: {}", e))?;
+    for version in from_version..to_version {
+        migrations[version as usize - 1](&mut document).map_err(|e| {
+            format!(
+                "Failed to migrate crosscode.toml from version {} to {}: {}",
+                version,
+                version + 1,
+                e
+            )
+        })?;
+        set_format_version(&mut document, version + 1);
+    }
+    let migrated = document.to_string();
+    // Never write something the current version can't load
+    toml::from_str::<TomlConfig>(&migrated)
+        .map_err(|e| format!("Migrated crosscode.toml is invalid: {}", e))?;
+
+    let mut migration = ConfigMigration {
+        from_version,
+        to_version,
+        diff: line_diff(&original, &migrated),
+        backup_path: None,
+    };
+    if dry_run || from_version == to_version {
+        return Ok(migration);
+    }
+
+    let backup = backup_path(project_path, from_version);
+    fs::copy(&path, &backup).map_err(|e| format!("Failed to back up crosscode.toml: {}", e))?;
+    fs::write(&path, migrated).map_err(|e| format!("Failed to write crosscode.toml: {}", e))?;
+    migration.backup_path = Some(backup.to_string_lossy().to_string());
+    Ok(migration)
+}
+
+// crosscode.toml.v1.bak, numbered if an earlier migration already left one
+fn backup_path(project_path: &Path, version: u32) -> PathBuf {
+    let base = project_path.join(format!("crosscode.toml.v{}.bak", version));
+    let mut path = base.clone();
+    let mut n = 1;
+    while path.exists() {
+        path = PathBuf::from(format!("{}.{}", base.display(), n));
+        n += 1;
+    }
+    path
+}
+
+fn set_format_version(document: &mut DocumentMut, version: u32) {
+    let decor = document
+        .get("format_version")
+        .and_then(|item| item.as_value())
+        .map(|value| value.decor().clone());
+    document["format_version"] = toml_edit::value(version as i64);
+    if let (Some(decor), Some(value)) = (decor, document["format_version"].as_value_mut()) {
+        *value.decor_mut() = decor;
+    }
+}
+
+// Longest common subsequence of lines, config files are small enough for the quadratic table
+fn line_diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            diff.push(format!(" {}", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            diff.push(format!("-{}", old[i]));
+            i += 1;
+        } else {
+            diff.push(format!("+{}", new[j]));
+            j += 1;
+        }
+    }
+    diff.join("\n")
+}
+
+#[tauri::command]
+pub fn preview_config_migration(folder: String) -> Result<ConfigMigration, String> {
+    migrate_project_config(&PathBuf::from(folder), MIGRATIONS, true)
+}
+
+#[tauri::command]
+pub fn migrate_config(folder: String) -> Result<ConfigMigration, String> {
+    migrate_project_config(&PathBuf::from(folder), MIGRATIONS, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1: &str = r#"# Project settings
+format_version = 1
+
+[project]
+version_num = "1"
+version_string = "1.0.0"
+identifier = "com.example.app" # renamed in version 2
+"#;
+
+    // A made up version 2 that renames project.identifier to project.bundle_id
+    fn rename_identifier(document: &mut DocumentMut) -> Result<(), String> {
+        let project = document["project"]
+            .as_table_mut()
+            .ok_or("[project] is missing".to_string())?;
+        let identifier = project
+            .remove("identifier")
+            .ok_or("project.identifier is missing".to_string())?;
+        project.insert("bundle_id", identifier);
+        Ok(())
+    }
+
+    fn project_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("crosscode-migrate-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("crosscode.toml"), V1).unwrap();
+        dir
+    }
+
+    #[test]
+    fn migrates_and_backs_up() {
+        let dir = project_dir("write");
+        let migration = migrate_project_config(&dir, &[rename_identifier], false).unwrap();
+        assert_eq!((migration.from_version, migration.to_version), (1, 2));
+
+        let migrated = fs::read_to_string(dir.join("crosscode.toml")).unwrap();
+        assert_eq!(read_format_version(&migrated).unwrap(), 2);
+        assert!(migrated.starts_with("# Project settings\n"));
+        assert!(migrated.contains("bundle_id = \"com.example.app\" # renamed in version 2"));
+        assert!(!migrated.contains("identifier ="));
+        assert!(migration
+            .diff
+            .contains("+bundle_id = \"com.example.app\" # renamed in version 2"));
+
+        let backup = PathBuf::from(migration.backup_path.unwrap());
+        assert_eq!(fs::read_to_string(backup).unwrap(), V1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn dry_run_leaves_the_file_alone() {
+        let dir = project_dir("dry-run");
+        let migration = migrate_project_config(&dir, &[rename_identifier], true).unwrap();
+        assert_eq!(migration.to_version, 2);
+        assert!(migration.backup_path.is_none());
+        assert_eq!(fs::read_to_string(dir.join("crosscode.toml")).unwrap(), V1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let dir = project_dir("newer");
+        fs::write(
+            dir.join("crosscode.toml"),
+            V1.replace("format_version = 1", "format_version = 3"),
+        )
+        .unwrap();
+        assert!(migrate_project_config(&dir, &[rename_identifier], false).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
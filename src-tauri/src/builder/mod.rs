@@ -1,6 +1,20 @@
+pub mod assets;
 pub mod config;
 pub mod crossplatform;
+pub mod dependencies;
+pub mod diagnostics;
+pub mod entitlements;
 pub mod icon;
+pub mod info_plist;
+pub mod jobs;
+pub mod macho;
+pub mod manifest;
+pub mod migrate;
+pub mod package;
 pub mod packer;
+pub mod project_validation;
 pub mod sdk;
 pub mod swift;
+pub mod symbols;
+pub mod validator;
+pub mod version;
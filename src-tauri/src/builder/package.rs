@@ -0,0 +1,461 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::builder::swift::SwiftBin;
+
+// The manifest as reported by swift package dump-package. SwiftPM encodes its enums as
+// single key objects whose value is an array of the associated values, e.g.
+// {"product": ["Name", "package", null, null]}, so this is read from the raw JSON rather
+// than derived.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SwiftPackageDump {
+    pub name: String,
+    pub tools_version: Option<String>,
+    pub platforms: Vec<SwiftPackagePlatform>,
+    pub products: Vec<SwiftPackageProduct>,
+    pub targets: Vec<SwiftPackageTarget>,
+    pub dependencies: Vec<SwiftPackageDependency>,
+    pub swift_language_modes: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SwiftPackagePlatform {
+    // Lowercase, e.g. "ios" or "macos"
+    pub platform_name: String,
+    pub version: String,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum ProductType {
+    Executable,
+    Library,
+    Plugin,
+    Macro,
+    Snippet,
+    Test,
+    Unknown,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SwiftPackageProduct {
+    pub name: String,
+    pub product_type: ProductType,
+    // automatic, static or dynamic, only for libraries
+    pub library_type: Option<String>,
+    pub targets: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum TargetType {
+    Regular,
+    Executable,
+    Test,
+    System,
+    Binary,
+    Plugin,
+    Macro,
+    Unknown,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SwiftPackageTarget {
+    pub name: String,
+    pub target_type: TargetType,
+    // Relative to the package, None for the default Sources/<name>
+    pub path: Option<String>,
+    pub dependencies: Vec<TargetDependency>,
+    pub resources: Vec<TargetResource>,
+    pub exclude: Vec<String>,
+    pub settings: Vec<BuildSetting>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum TargetDependencyKind {
+    // .target(name:), always in this package
+    Target,
+    // .product(name:package:), from a package dependency
+    Product,
+    // A plain string, either of the above
+    ByName,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetDependency {
+    pub kind: TargetDependencyKind,
+    pub name: String,
+    pub package: Option<String>,
+    // Only linked on these platforms, empty for all of them
+    pub platforms: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetResource {
+    // process, copy or embedInCode
+    pub rule: String,
+    pub path: String,
+    pub localization: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildSetting {
+    // swift, c, cxx or linker
+    pub tool: String,
+    // e.g. define, unsafeFlags or enableUpcomingFeature
+    pub kind: String,
+    pub values: Vec<String>,
+    pub platforms: Vec<String>,
+    // debug or release
+    pub configuration: Option<String>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum DependencyKind {
+    SourceControl,
+    FileSystem,
+    Registry,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SwiftPackageDependency {
+    pub identity: String,
+    pub kind: DependencyKind,
+    // The URL, path or registry identity
+    pub location: String,
+    pub requirement: Option<DependencyRequirement>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum DependencyRequirement {
+    #[serde(rename_all = "camelCase")]
+    Range {
+        lower_bound: String,
+        upper_bound: String,
+    },
+    Exact {
+        version: String,
+    },
+    Branch {
+        name: String,
+    },
+    Revision {
+        revision: String,
+    },
+}
+
+impl SwiftPackageDump {
+    pub fn load(swift: &SwiftBin, project_path: &Path) -> Result<Self, String> {
+        let raw_package = swift
+            .command()
+            .arg("package")
+            .arg("dump-package")
+            .current_dir(project_path)
+            .output()
+            .map_err(|e| format!("Failed to execute swift command: {}", e))?;
+        if !raw_package.status.success() {
+            return Err(format!(
+                "Failed to dump package: {}",
+                String::from_utf8_lossy(&raw_package.stderr)
+            ));
+        }
+        let json: Value = serde_json::from_slice(&raw_package.stdout)
+            .map_err(|e| format!("Failed to parse package dump: {}", e))?;
+        Self::from_json(&json)
+    }
+
+    pub fn from_json(json: &Value) -> Result<Self, String> {
+        let name = string(json, "name").ok_or("Package dump has no name".to_string())?;
+        let targets = array(json, "targets")
+            .iter()
+            .map(parse_target)
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(SwiftPackageDump {
+            name,
+            tools_version: json
+                .get("toolsVersion")
+                .and_then(|v| v.get("_version"))
+                .and_then(|v| v.as_str())
+                .map(|v| v.to_string()),
+            platforms: array(json, "platforms")
+                .iter()
+                .filter_map(|platform| {
+                    Some(SwiftPackagePlatform {
+                        platform_name: string(platform, "platformName")?,
+                        version: string(platform, "version")?,
+                    })
+                })
+                .collect(),
+            products: array(json, "products")
+                .iter()
+                .filter_map(parse_product)
+                .collect(),
+            targets,
+            dependencies: array(json, "dependencies")
+                .iter()
+                .filter_map(parse_dependency)
+                .collect(),
+            swift_language_modes: json
+                .get("swiftLanguageModes")
+                .or(json.get("swiftLanguageVersions"))
+                .map(flatten_strings)
+                .unwrap_or_default(),
+        })
+    }
+
+    pub fn platform_version(&self, name: &str) -> Option<String> {
+        self.platforms
+            .iter()
+            .find(|p| p.platform_name == name)
+            .map(|p| p.version.clone())
+    }
+
+    pub fn target(&self, name: &str) -> Option<&SwiftPackageTarget> {
+        self.targets.iter().find(|t| t.name == name)
+    }
+
+    // Declared executable products, plus the ones SwiftPM adds for executable targets that
+    // no declared product covers
+    pub fn executable_products(&self) -> Vec<String> {
+        let declared: Vec<&SwiftPackageProduct> = self
+            .products
+            .iter()
+            .filter(|p| p.product_type == ProductType::Executable)
+            .collect();
+        let implicit = self
+            .targets
+            .iter()
+            .filter(|t| t.target_type == TargetType::Executable)
+            .filter(|t| !declared.iter().any(|p| p.targets.contains(&t.name)))
+            .map(|t| t.name.clone());
+        declared
+            .iter()
+            .map(|p| p.name.clone())
+            .chain(implicit)
+            .collect()
+    }
+}
+
+fn parse_product(product: &Value) -> Option<SwiftPackageProduct> {
+    let (product_type, library_type) = match enum_case(product.get("type")?) {
+        Some(("executable", _)) => (ProductType::Executable, None),
+        Some(("library", values)) => (
+            ProductType::Library,
+            flatten_strings(values).into_iter().next(),
+        ),
+        Some(("plugin", _)) => (ProductType::Plugin, None),
+        Some(("macro", _)) => (ProductType::Macro, None),
+        Some(("snippet", _)) => (ProductType::Snippet, None),
+        Some(("test", _)) => (ProductType::Test, None),
+        _ => (ProductType::Unknown, None),
+    };
+    Some(SwiftPackageProduct {
+        name: string(product, "name")?,
+        product_type,
+        library_type,
+        targets: array(product, "targets")
+            .iter()
+            .filter_map(|t| t.as_str().map(|t| t.to_string()))
+            .collect(),
+    })
+}
+
+fn parse_target(target: &Value) -> Result<SwiftPackageTarget, String> {
+    let name = string(target, "name").ok_or("Package dump has a target without a name")?;
+    let target_type = match target.get("type").and_then(|t| t.as_str()) {
+        Some("regular") => TargetType::Regular,
+        Some("executable") => TargetType::Executable,
+        Some("test") => TargetType::Test,
+        Some("system") => TargetType::System,
+        Some("binary") => TargetType::Binary,
+        Some("plugin") => TargetType::Plugin,
+        Some("macro") => TargetType::Macro,
+        _ => TargetType::Unknown,
+    };
+    Ok(SwiftPackageTarget {
+        name,
+        target_type,
+        path: string(target, "path"),
+        dependencies: array(target, "dependencies")
+            .iter()
+            .filter_map(parse_target_dependency)
+            .collect(),
+        resources: array(target, "resources")
+            .iter()
+            .filter_map(|resource| {
+                let (rule, options) = enum_case(resource.get("rule")?)?;
+                Some(TargetResource {
+                    rule: rule.to_string(),
+                    path: string(resource, "path")?,
+                    localization: string(options, "localization"),
+                })
+            })
+            .collect(),
+        exclude: array(target, "exclude")
+            .iter()
+            .filter_map(|e| e.as_str().map(|e| e.to_string()))
+            .collect(),
+        settings: array(target, "settings")
+            .iter()
+            .filter_map(parse_setting)
+            .collect(),
+    })
+}
+
+// {"byName": [name, condition]}, {"target": [name, condition]} or
+// {"product": [name, package, moduleAliases, condition]}
+fn parse_target_dependency(dependency: &Value) -> Option<TargetDependency> {
+    let (case, values) = enum_case(dependency)?;
+    let values = values.as_array()?;
+    let name = values.first()?.as_str()?.to_string();
+    let (kind, package) = match case {
+        "byName" => (TargetDependencyKind::ByName, None),
+        "target" => (TargetDependencyKind::Target, None),
+        "product" => (
+            TargetDependencyKind::Product,
+            values
+                .get(1)
+                .and_then(|p| p.as_str())
+                .map(|p| p.to_string()),
+        ),
+        _ => return None,
+    };
+    Some(TargetDependency {
+        kind,
+        name,
+        package,
+        platforms: values.last().map(condition_platforms).unwrap_or_default(),
+    })
+}
+
+// {"tool": "swift", "kind": {"define": {"_0": "DEBUG"}}, "condition": ...}, or the older
+// {"tool": "swift", "name": "define", "value": ["DEBUG"]}
+fn parse_setting(setting: &Value) -> Option<BuildSetting> {
+    let (kind, values) = match setting.get("kind").and_then(enum_case) {
+        Some((kind, values)) => (kind.to_string(), flatten_strings(values)),
+        None => (
+            string(setting, "name")?,
+            setting
+                .get("value")
+                .map(flatten_strings)
+                .unwrap_or_default(),
+        ),
+    };
+    let condition = setting.get("condition").unwrap_or(&Value::Null);
+    Some(BuildSetting {
+        tool: string(setting, "tool")?,
+        kind,
+        values,
+        platforms: condition_platforms(condition),
+        configuration: string(condition, "config"),
+    })
+}
+
+fn parse_dependency(dependency: &Value) -> Option<SwiftPackageDependency> {
+    let (case, values) = enum_case(dependency)?;
+    let details = values.as_array()?.first()?;
+    let identity = string(details, "identity")?;
+    let (kind, location) = match case {
+        "sourceControl" => {
+            // {"remote": [{"urlString": url}]}, or {"remote": [url]} in older versions
+            let (_, locations) = enum_case(details.get("location")?)?;
+            let location = locations.as_array()?.first()?;
+            let location = location
+                .as_str()
+                .map(|l| l.to_string())
+                .or(string(location, "urlString"))?;
+            (DependencyKind::SourceControl, location)
+        }
+        "fileSystem" => (DependencyKind::FileSystem, string(details, "path")?),
+        "registry" => (DependencyKind::Registry, identity.clone()),
+        _ => return None,
+    };
+    Some(SwiftPackageDependency {
+        identity,
+        kind,
+        location,
+        requirement: details.get("requirement").and_then(parse_requirement),
+    })
+}
+
+fn parse_requirement(requirement: &Value) -> Option<DependencyRequirement> {
+    let (case, values) = enum_case(requirement)?;
+    let first = values.as_array()?.first()?;
+    Some(match case {
+        "range" => DependencyRequirement::Range {
+            lower_bound: string(first, "lowerBound")?,
+            upper_bound: string(first, "upperBound")?,
+        },
+        "exact" => DependencyRequirement::Exact {
+            version: first.as_str()?.to_string(),
+        },
+        "branch" => DependencyRequirement::Branch {
+            name: first.as_str()?.to_string(),
+        },
+        "revision" => DependencyRequirement::Revision {
+            revision: first.as_str()?.to_string(),
+        },
+        _ => return None,
+    })
+}
+
+fn condition_platforms(condition: &Value) -> Vec<String> {
+    array(condition, "platformNames")
+        .iter()
+        .filter_map(|p| p.as_str().map(|p| p.to_string()))
+        .collect()
+}
+
+// A Swift enum case, {"case": associated values}
+fn enum_case(value: &Value) -> Option<(&str, &Value)> {
+    let object = value.as_object()?;
+    if object.len() != 1 {
+        return None;
+    }
+    object.iter().next().map(|(k, v)| (k.as_str(), v))
+}
+
+// Associated values come as strings, arrays, {"_0": value} or cases without values like
+// {"v6": {}}
+fn flatten_strings(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(s) => vec![s.clone()],
+        Value::Array(values) => values.iter().flat_map(flatten_strings).collect(),
+        Value::Object(object) => match object.get("_0") {
+            Some(inner) => flatten_strings(inner),
+            None => object.keys().cloned().collect(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+fn string(value: &Value, key: &str) -> Option<String> {
+    value.get(key)?.as_str().map(|s| s.to_string())
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value
+        .get(key)
+        .and_then(|v| v.as_array())
+        .map(|v| v.as_slice())
+        .unwrap_or(&[])
+}
+
+#[tauri::command]
+pub fn dump_package(folder: String, toolchain_path: String) -> Result<SwiftPackageDump, String> {
+    let swift = SwiftBin::new(&toolchain_path)?;
+    SwiftPackageDump::load(&swift, &PathBuf::from(folder))
+}
@@ -1,63 +1,107 @@
 use std::{
     fs::{self, File},
-    io::prelude::*,
-    path::PathBuf,
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
 };
 
 use dircpy::CopyBuilder;
-use zip::write::SimpleFileOptions;
+use plist::{Dictionary, Value};
+use rayon::prelude::*;
+use zip::{write::SimpleFileOptions, CompressionMethod, DateTime, ZipArchive, ZipWriter};
 
-use crate::builder::config::{BuildSettings, ProjectConfig};
+use crate::builder::{
+    assets::compile_asset_catalogs,
+    config::{BuildSettings, ExtensionConfig, PlistFormat, ProjectConfig, TargetPlatform},
+    diagnostics::Severity,
+    info_plist::{build_extension_info_plist, build_info_plist, write_info_plist},
+    jobs::JobProcess,
+    macho::{is_macho, read_macho},
+    swift::SwiftBin,
+    symbols::{archive_dsym, copy_dsym, strip_executable},
+    validator::validate_bundle,
+};
+
+// Where things go inside a .app or .appex. macOS bundles keep everything under Contents/,
+// iOS bundles are flat.
+struct BundleLayout {
+    contents: PathBuf,
+    executables: PathBuf,
+    resources: PathBuf,
+}
+
+impl BundleLayout {
+    fn new(bundle: &Path, platform: TargetPlatform) -> Self {
+        if platform == TargetPlatform::Macos {
+            let contents = bundle.join("Contents");
+            BundleLayout {
+                executables: contents.join("MacOS"),
+                resources: contents.join("Resources"),
+                contents,
+            }
+        } else {
+            BundleLayout {
+                contents: bundle.to_path_buf(),
+                executables: bundle.to_path_buf(),
+                resources: bundle.to_path_buf(),
+            }
+        }
+    }
+
+    fn create(&self) -> Result<(), String> {
+        for dir in [&self.contents, &self.executables, &self.resources] {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        Ok(())
+    }
+}
 
 pub fn pack(
     project_path: PathBuf,
     config: &ProjectConfig,
     build_settings: &BuildSettings,
+    swift_bin: &SwiftBin,
+    job: &JobProcess,
+    progress: impl Fn(String),
 ) -> Result<PathBuf, String> {
-    let workdir = project_path.join(".crosscode").join("Payload");
+    // A cancel is picked up between steps instead of after the whole app is packed
+    job.check_cancelled()?;
+    let workdir = output_dir(&project_path, build_settings);
     if !workdir.exists() {
         std::fs::create_dir_all(&workdir)
             .map_err(|e| format!("Failed to create work directory: {}", e))?;
     }
-    let app_path = workdir.join(format!("{}.app", config.product));
-    if app_path.exists() {
-        std::fs::remove_dir_all(&app_path)
+    let platform = build_settings.platform;
+    // The IPA is zipped from the whole Payload, so it may only hold the app being packed,
+    // not one of the other [[apps]] from an earlier build
+    let stale_apps: Vec<PathBuf> = match platform {
+        TargetPlatform::Ios => fs::read_dir(&workdir)
+            .map_err(|e| format!("Failed to read {}: {}", workdir.display(), e))?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "app"))
+            .collect(),
+        _ => vec![workdir.join(format!("{}.app", config.product))],
+    };
+    for app in stale_apps.iter().filter(|app| app.exists()) {
+        std::fs::remove_dir_all(app)
             .map_err(|e| format!("Failed to remove existing app directory: {}", e))?;
     }
-    std::fs::create_dir_all(&app_path)
-        .map_err(|e| format!("Failed to create app directory: {}", e))?;
+    let app_path = workdir.join(format!("{}.app", config.product));
+    let layout = BundleLayout::new(&app_path, platform);
+    layout.create()?;
 
-    let exec = project_path
-        .join(".build")
-        .join("arm64-apple-ios")
-        .join(if build_settings.debug {
-            "debug"
-        } else {
-            "release"
-        })
-        .join(&config.product);
+    let build_dir = build_products_dir(&project_path, build_settings);
+    let exec = build_dir.join(&config.product);
 
     if !exec.exists() {
         return Err(format!("Executable not found at: {}", exec.display()));
     }
 
-    fs::copy(exec, app_path.join(&config.product))
+    fs::copy(exec, layout.executables.join(&config.product))
         .map_err(|e| format!("Failed to copy executable: {}", e))?;
 
-    // TODO: Create default Info.plist if it doesn't exist
-    let info_plist = project_path.join("Info.plist");
-    if !info_plist.exists() {
-        return Err(format!("Info.plist not found at: {}", info_plist.display()));
-    }
-
-    let info_content = fs::read_to_string(&info_plist)
-        .map_err(|e| format!("Failed to read Info.plist: {}", e))?
-        .replace("[[bundle_id]]", &config.bundle_id)
-        .replace("[[product]]", &config.product)
-        .replace("[[version_num]]", &config.version_num)
-        .replace("[[version_string]]", &config.version_string);
-    fs::write(&app_path.join("Info.plist"), info_content)
-        .map_err(|e| format!("Failed to write Info.plist: {}", e))?;
+    let mut info = build_info_plist(&project_path, config, platform)?;
 
     let resources = project_path.join("Resources");
 
@@ -66,13 +110,352 @@ pub fn pack(
             .map_err(|e| format!("Failed to create Resources directory: {}", e))?;
     }
 
-    CopyBuilder::new(&resources, &app_path)
+    CopyBuilder::new(&resources, &layout.resources)
         .run()
         .map_err(|e| format!("Failed to copy resources: {}", e))?;
 
+    job.check_cancelled()?;
+    let assets = compile_asset_catalogs(&project_path, &layout.resources)?;
+    for warning in assets.warnings {
+        progress(format!("Warning: {}", warning));
+    }
+    // Icons from a catalog replace whatever the Info.plist listed. macOS wants an .icns
+    // instead, which isn't generated.
+    if platform != TargetPlatform::Macos {
+        for (key, value) in assets.info_plist {
+            info.insert(key, value);
+        }
+    }
+    write_info_plist(
+        &info,
+        &layout.contents.join("Info.plist"),
+        config.info_plist_format,
+    )?;
+
+    copy_resource_bundles(&build_dir, &layout.resources)?;
+
+    let plugins = layout.contents.join("PlugIns");
+    let mut executables = vec![layout.executables.join(&config.product)];
+    if !config.extensions.is_empty() {
+        fs::create_dir_all(&plugins)
+            .map_err(|e| format!("Failed to create PlugIns directory: {}", e))?;
+        for extension in &config.extensions {
+            job.check_cancelled()?;
+            executables.push(pack_extension(
+                &project_path,
+                &build_dir,
+                &plugins,
+                config,
+                extension,
+                platform,
+            )?);
+        }
+    }
+
+    job.check_cancelled()?;
+    embed_dynamic_dependencies(&build_dir, &layout, &executables, config, platform)?;
+
+    // Missing symbols don't make the app any less usable, so these only warn
+    for executable in &executables {
+        job.check_cancelled()?;
+        match archive_dsym(swift_bin, &project_path, executable) {
+            Ok(dsym) => {
+                progress(format!("Saved dSYM to {}", dsym.display()));
+                // The IPA is zipped next to Payload
+                if let (TargetPlatform::Ios, Some(ipa_dir)) = (platform, workdir.parent()) {
+                    if let Err(e) = copy_dsym(&dsym, ipa_dir) {
+                        progress(format!("Warning: {}", e));
+                    }
+                }
+            }
+            Err(e) => {
+                progress(format!("Warning: No dSYM was saved: {}", e));
+                continue;
+            }
+        }
+        if !build_settings.debug {
+            if let Err(e) = strip_executable(swift_bin, executable) {
+                progress(format!(
+                    "Warning: {} executable not stripped: {}",
+                    executable
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    e
+                ));
+            }
+        }
+    }
+
+    job.check_cancelled()?;
+    let issues = validate_bundle(&app_path, platform);
+    for issue in &issues {
+        progress(issue.to_string());
+    }
+    let errors = issues
+        .iter()
+        .filter(|i| i.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        return Err(format!(
+            "The app bundle has {} problem(s) that would make the install fail",
+            errors
+        ));
+    }
+
     Ok(app_path)
 }
 
+// iOS device builds go in Payload/ so they can be zipped into an IPA as is. Each named
+// configuration gets its own directory, so its app and IPA don't replace the default ones.
+pub fn output_dir(project_path: &Path, build_settings: &BuildSettings) -> PathBuf {
+    let mut crosscode = project_path.join(".crosscode");
+    if let Some(configuration) = &build_settings.configuration {
+        crosscode = crosscode.join("configurations").join(configuration);
+    }
+    match build_settings.platform {
+        TargetPlatform::Ios => crosscode.join("Payload"),
+        _ => crosscode.join("Products").join(build_settings.sdk_triple()),
+    }
+}
+
+pub fn build_products_dir(project_path: &Path, build_settings: &BuildSettings) -> PathBuf {
+    project_path
+        .join(".build")
+        .join(build_settings.sdk_triple())
+        .join(if build_settings.debug {
+            "debug"
+        } else {
+            "release"
+        })
+}
+
+// SwiftPM emits a <Package>_<Target>.bundle next to the executable for every target
+// with resources (dependencies included), and Bundle.module looks for it in the root of
+// Bundle.main, which for an extension is the .appex
+fn copy_resource_bundles(build_dir: &Path, app_path: &Path) -> Result<(), String> {
+    let entries =
+        fs::read_dir(build_dir).map_err(|e| format!("Failed to read build directory: {}", e))?;
+    for entry in entries {
+        let path = entry
+            .map_err(|e| format!("Failed to read build directory entry: {}", e))?
+            .path();
+        if !path.is_dir() || path.extension().is_none_or(|ext| ext != "bundle") {
+            continue;
+        }
+        let name = path.file_name().unwrap();
+        CopyBuilder::new(&path, app_path.join(name))
+            .overwrite(true)
+            .run()
+            .map_err(|e| {
+                format!(
+                    "Failed to copy resource bundle {}: {}",
+                    name.to_string_lossy(),
+                    e
+                )
+            })?;
+    }
+    Ok(())
+}
+
+// Follows the load commands of every executable (and of whatever gets embedded) and copies
+// the frameworks and dylibs they expect to find through @rpath into Frameworks/
+fn embed_dynamic_dependencies(
+    build_dir: &Path,
+    layout: &BundleLayout,
+    executables: &[PathBuf],
+    config: &ProjectConfig,
+    platform: TargetPlatform,
+) -> Result<(), String> {
+    let app_path = layout.contents.as_path();
+    let frameworks = app_path.join("Frameworks");
+    let mut queue = executables.to_vec();
+    let mut embedded: Vec<String> = Vec::new();
+
+    while let Some(binary) = queue.pop() {
+        let slices = read_macho(&binary)?;
+        let binary_name = binary.strip_prefix(app_path).unwrap_or(&binary).display();
+        for dylib in slices.iter().flat_map(|s| s.dylibs.iter()) {
+            if dylib.starts_with("/usr/lib/") || dylib.starts_with("/System/") {
+                continue;
+            }
+            let relative = match [
+                "@rpath/",
+                "@executable_path/Frameworks/",
+                "@loader_path/Frameworks/",
+            ]
+            .iter()
+            .find_map(|prefix| dylib.strip_prefix(prefix))
+            {
+                Some(relative) => relative,
+                None => {
+                    return Err(format!(
+                        "{} links {}, which will not exist on the device",
+                        binary_name, dylib
+                    ))
+                }
+            };
+            // Frameworks are copied whole, dylibs on their own
+            let item = match relative.split_once(".framework/") {
+                Some((name, _)) => format!("{}.framework", name),
+                None => relative.to_string(),
+            };
+            if embedded.contains(&item) {
+                continue;
+            }
+
+            let source = [build_dir.to_path_buf(), build_dir.join("PackageFrameworks")]
+                .iter()
+                .map(|dir| dir.join(&item))
+                .find(|path| path.exists());
+            let source = match source {
+                Some(source) => source,
+                // The Swift runtime is part of the OS
+                None if item.starts_with("libswift") => continue,
+                None => {
+                    return Err(format!(
+                        "{} links {}, but it was not found in {}",
+                        binary_name,
+                        dylib,
+                        build_dir.display()
+                    ))
+                }
+            };
+
+            fs::create_dir_all(&frameworks)
+                .map_err(|e| format!("Failed to create Frameworks directory: {}", e))?;
+            let dest = frameworks.join(&item);
+            if source.is_dir() {
+                CopyBuilder::new(&source, &dest)
+                    .overwrite(true)
+                    .run()
+                    .map_err(|e| format!("Failed to embed {}: {}", item, e))?;
+                prepare_framework(&dest, config, platform)?;
+            } else {
+                fs::copy(&source, &dest).map_err(|e| format!("Failed to embed {}: {}", item, e))?;
+            }
+            embedded.push(item);
+            queue.push(frameworks.join(relative));
+        }
+    }
+
+    if embedded.is_empty() {
+        return Ok(());
+    }
+    for executable in executables {
+        let rpaths: Vec<String> = read_macho(executable)?
+            .into_iter()
+            .flat_map(|s| s.rpaths)
+            .collect();
+        let (app_rpath, extension_rpath) = platform.framework_rpaths();
+        let expected = if executable.parent() == Some(layout.executables.as_path()) {
+            app_rpath
+        } else {
+            extension_rpath
+        };
+        if !rpaths.iter().any(|r| r.trim_end_matches('/') == expected) {
+            return Err(format!(
+                "{} is missing the {} rpath needed to load embedded frameworks",
+                executable
+                    .strip_prefix(app_path)
+                    .unwrap_or(executable)
+                    .display(),
+                expected
+            ));
+        }
+    }
+    Ok(())
+}
+
+// The signer re-signs every bundle in Frameworks/, which needs an Info.plist to treat the
+// framework as one. Vendor signatures and build-only files are dropped on the way in.
+fn prepare_framework(
+    framework: &Path,
+    config: &ProjectConfig,
+    platform: TargetPlatform,
+) -> Result<(), String> {
+    for unused in ["_CodeSignature", "Headers", "PrivateHeaders", "Modules"] {
+        let path = framework.join(unused);
+        if path.exists() {
+            fs::remove_dir_all(&path)
+                .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+        }
+    }
+
+    // macOS frameworks are versioned and already have Resources/Info.plist
+    let info_path = framework.join("Info.plist");
+    if info_path.exists() || platform == TargetPlatform::Macos {
+        return Ok(());
+    }
+    let name = framework
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut info = Dictionary::new();
+    info.insert(
+        "CFBundleExecutable".to_string(),
+        Value::String(name.clone()),
+    );
+    info.insert(
+        "CFBundleIdentifier".to_string(),
+        Value::String(format!("{}.framework.{}", config.bundle_id, name)),
+    );
+    info.insert("CFBundleName".to_string(), Value::String(name));
+    info.insert(
+        "CFBundleInfoDictionaryVersion".to_string(),
+        Value::String("6.0".to_string()),
+    );
+    info.insert(
+        "CFBundlePackageType".to_string(),
+        Value::String("FMWK".to_string()),
+    );
+    info.insert(
+        "CFBundleVersion".to_string(),
+        Value::String(config.version_num.clone()),
+    );
+    info.insert(
+        "MinimumOSVersion".to_string(),
+        Value::String(config.platform.deployment_target.clone()),
+    );
+    write_info_plist(&info, &info_path, PlistFormat::Xml)
+}
+
+// Returns the path of the extension's executable
+fn pack_extension(
+    project_path: &Path,
+    build_dir: &Path,
+    plugins: &Path,
+    config: &ProjectConfig,
+    extension: &ExtensionConfig,
+    platform: TargetPlatform,
+) -> Result<PathBuf, String> {
+    let appex_path = plugins.join(extension.bundle_name());
+    let layout = BundleLayout::new(&appex_path, platform);
+    layout.create()?;
+
+    let exec = build_dir.join(&extension.target);
+    if !exec.exists() {
+        return Err(format!(
+            "Extension executable not found at: {}",
+            exec.display()
+        ));
+    }
+    let dest = layout.executables.join(&extension.target);
+    fs::copy(&exec, &dest)
+        .map_err(|e| format!("Failed to copy {} executable: {}", extension.target, e))?;
+    copy_resource_bundles(build_dir, &layout.resources)?;
+
+    let info = build_extension_info_plist(project_path, config, extension, platform)?;
+    write_info_plist(
+        &info,
+        &layout.contents.join("Info.plist"),
+        config.info_plist_format,
+    )?;
+    Ok(dest)
+}
+
+// Entries are sorted, get the zip epoch as their timestamp and only executables are marked
+// executable, so packing the same app twice gives a byte-identical IPA
 pub fn zip_ipa(app: PathBuf, config: &ProjectConfig) -> Result<PathBuf, String> {
     let payload = app.parent().unwrap_or(&PathBuf::from(".")).to_path_buf();
 
@@ -87,55 +470,125 @@ pub fn zip_ipa(app: PathBuf, config: &ProjectConfig) -> Result<PathBuf, String>
         .parent()
         .unwrap()
         .join(format!("{}.ipa", config.product));
-    let zip_file = File::create(&ipa_path)
-        .map_err(|e| format!("Failed to create zip file in payload directory: {}", e))?;
-    let mut zip = zip::ZipWriter::new(zip_file);
-    let options = SimpleFileOptions::default()
-        .compression_method(zip::CompressionMethod::Deflated)
-        .unix_permissions(0o755);
-    let walkdir = walkdir::WalkDir::new(&payload)
-        .into_iter()
-        .filter_map(|e| e.ok());
-
     let prefix = payload.as_path().parent().ok_or(format!(
         "Failed to get parent directory of payload: {}",
         payload.display()
     ))?;
 
-    // https://github.com/zip-rs/zip2/blob/6c78fe381da074610d99e2d59546b0530bcb6e54/examples/write_dir.rs
-    let mut buffer = Vec::new();
-    for entry in walkdir {
+    let mut entries = Vec::new();
+    for entry in walkdir::WalkDir::new(&payload).sort_by_file_name() {
+        let entry = entry.map_err(|e| format!("Failed to read payload directory: {}", e))?;
         let path = entry.path();
         let name = path
             .strip_prefix(prefix)
             .map_err(|e| format!("Failed to strip prefix from path: {}", e))?;
-        let path_as_string = name
-            .to_str()
-            .map(str::to_owned)
-            .ok_or_else(|| format!("Failed to convert path to string: {}", path.display()))?;
-
-        // Write file or directory explicitly
-        // Some unzip tools unzip files with directory paths correctly, some do not!
-        if path.is_file() {
-            zip.start_file(path_as_string, options)
-                .map_err(|e| format!("Failed to start file {}: {}", path.display(), e))?;
-            let mut f = File::open(path)
-                .map_err(|e| format!("Failed to open file {}: {}", path.display(), e))?;
-
-            f.read_to_end(&mut buffer)
-                .map_err(|e| format!("Failed to read file {}: {}", path.display(), e))?;
-            zip.write_all(&buffer)
-                .map_err(|e| format!("Failed to write file {}: {}", path.display(), e))?;
-            buffer.clear();
-        } else if !name.as_os_str().is_empty() {
-            // Only if not root! Avoids path spec / warning
-            // and mapname conversion failed error on unzip
-            zip.add_directory(path_as_string, options)
-                .map_err(|e| format!("Failed to add directory {}: {}", path.display(), e))?;
-        }
+        // Zip paths always use forward slashes, even when packing on Windows
+        let name = name
+            .components()
+            .map(|c| c.as_os_str().to_str())
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| format!("Failed to convert path to string: {}", path.display()))?
+            .join("/");
+        entries.push(IpaEntry {
+            path: path.to_path_buf(),
+            name,
+            directory: path.is_dir(),
+        });
+    }
+
+    let level = config.packaging.compression_level();
+    let options = if level == 0 {
+        SimpleFileOptions::default().compression_method(CompressionMethod::Stored)
+    } else {
+        SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .compression_level(Some(level as i64))
+    }
+    .last_modified_time(DateTime::default());
+
+    // Each thread compresses a run of files into its own part file, the parts are then
+    // copied into the IPA in order without being compressed again
+    let chunk_size = entries.len().div_ceil(rayon::current_num_threads()).max(1);
+    let parts: Vec<PathBuf> = (0..entries.len().div_ceil(chunk_size))
+        .map(|i| ipa_path.with_extension(format!("ipa.part{}", i)))
+        .collect();
+    let result = entries
+        .par_chunks(chunk_size)
+        .zip(parts.par_iter())
+        .try_for_each(|(chunk, part)| write_ipa_part(part, chunk, options))
+        .and_then(|_| merge_ipa_parts(&ipa_path, entries.chunks(chunk_size), &parts, options));
+    for part in &parts {
+        fs::remove_file(part).ok();
     }
+    result?;
 
+    Ok(ipa_path)
+}
+
+struct IpaEntry {
+    path: PathBuf,
+    name: String,
+    directory: bool,
+}
+
+fn write_ipa_part(
+    part: &Path,
+    entries: &[IpaEntry],
+    options: SimpleFileOptions,
+) -> Result<(), String> {
+    let file =
+        File::create(part).map_err(|e| format!("Failed to create {}: {}", part.display(), e))?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    for entry in entries.iter().filter(|e| !e.directory) {
+        let permissions = if is_macho(&entry.path) { 0o755 } else { 0o644 };
+        zip.start_file(entry.name.as_str(), options.unix_permissions(permissions))
+            .map_err(|e| format!("Failed to start file {}: {}", entry.name, e))?;
+        let mut f = File::open(&entry.path)
+            .map_err(|e| format!("Failed to open file {}: {}", entry.path.display(), e))?;
+        io::copy(&mut f, &mut zip)
+            .map_err(|e| format!("Failed to write file {}: {}", entry.name, e))?;
+    }
     zip.finish()
+        .and_then(|mut writer| writer.flush().map_err(Into::into))
+        .map_err(|e| format!("Failed to finish {}: {}", part.display(), e))?;
+    Ok(())
+}
+
+// Raw copies are always marked as regular files, so directories are added here instead of
+// in the parts
+fn merge_ipa_parts<'a>(
+    ipa_path: &Path,
+    chunks: impl Iterator<Item = &'a [IpaEntry]>,
+    parts: &[PathBuf],
+    options: SimpleFileOptions,
+) -> Result<(), String> {
+    let file = File::create(ipa_path)
+        .map_err(|e| format!("Failed to create zip file in payload directory: {}", e))?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    for (chunk, part) in chunks.zip(parts) {
+        let file =
+            File::open(part).map_err(|e| format!("Failed to open {}: {}", part.display(), e))?;
+        let mut archive = ZipArchive::new(BufReader::new(file))
+            .map_err(|e| format!("Failed to read {}: {}", part.display(), e))?;
+        let mut index = 0;
+        for entry in chunk {
+            // Write directories explicitly
+            // Some unzip tools unzip files with directory paths correctly, some do not!
+            if entry.directory {
+                zip.add_directory(entry.name.as_str(), options.unix_permissions(0o755))
+                    .map_err(|e| format!("Failed to add directory {}: {}", entry.name, e))?;
+                continue;
+            }
+            let file = archive
+                .by_index_raw(index)
+                .map_err(|e| format!("Failed to read {}: {}", part.display(), e))?;
+            zip.raw_copy_file(file)
+                .map_err(|e| format!("Failed to write file {}: {}", entry.name, e))?;
+            index += 1;
+        }
+    }
+    zip.finish()
+        .and_then(|mut writer| writer.flush().map_err(Into::into))
         .map_err(|e| format!("Failed to finish zip file: {}", e))?;
-    Ok(ipa_path)
+    Ok(())
 }
@@ -0,0 +1,531 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::builder::{
+    config::{ProjectConfig, TomlConfig, DEFAULT_DEPLOYMENT_TARGET, FORMAT_VERSION},
+    diagnostics::Severity,
+    manifest::{
+        code_mask, indentation, line_start, manifest_layout, manifest_path, split_items,
+        starts_line, write_manifest,
+    },
+    migrate::{migrate_project_config, read_format_version, MIGRATIONS},
+    package::SwiftPackageDump,
+    swift::SwiftBin,
+};
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+pub enum FindingKind {
+    MissingProject,
+    MissingPackage,
+    MissingConfig,
+    InvalidConfig,
+    UnsupportedFormatVersion,
+    OutdatedFormatVersion,
+    InvalidToolchain,
+    InvalidPackage,
+    MissingExecutable,
+    MissingPlatform,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationFinding {
+    pub kind: FindingKind,
+    pub severity: Severity,
+    pub message: String,
+    pub file: Option<String>,
+    // 1-based, like compiler diagnostics
+    pub line: Option<usize>,
+    pub fix: Option<ValidationFix>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationFix {
+    pub description: String,
+    pub action: FixAction,
+}
+
+// Sent back as is to apply_validation_fix
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum FixAction {
+    #[serde(rename_all = "camelCase")]
+    CreateConfig {
+        bundle_id: String,
+    },
+    MigrateConfig,
+    SetProduct {
+        product: String,
+    },
+    AddIosPlatform {
+        version: String,
+    },
+}
+
+impl ValidationFinding {
+    fn new(kind: FindingKind, severity: Severity, message: impl Into<String>) -> Self {
+        ValidationFinding {
+            kind,
+            severity,
+            message: message.into(),
+            file: None,
+            line: None,
+            fix: None,
+        }
+    }
+
+    fn at(mut self, file: &str, line: Option<usize>) -> Self {
+        self.file = Some(file.to_string());
+        self.line = line;
+        self
+    }
+
+    fn with_fix(mut self, description: impl Into<String>, action: FixAction) -> Self {
+        self.fix = Some(ValidationFix {
+            description: description.into(),
+            action,
+        });
+        self
+    }
+}
+
+// An empty list means the project is fine. Checks that depend on an earlier one, like
+// Package.swift needing a working toolchain, are skipped when it fails.
+pub fn validate_project_dir(project_path: &Path, toolchain_path: &str) -> Vec<ValidationFinding> {
+    let mut findings = Vec::new();
+    if !project_path.is_dir() {
+        findings.push(ValidationFinding::new(
+            FindingKind::MissingProject,
+            Severity::Error,
+            format!("{} does not exist", project_path.display()),
+        ));
+        return findings;
+    }
+
+    let toml_config = check_config(project_path, &mut findings);
+    if !project_path.join("Package.swift").exists() {
+        findings.push(ValidationFinding::new(
+            FindingKind::MissingPackage,
+            Severity::Error,
+            "Package.swift is missing, this does not appear to be a Swift package",
+        ));
+        return findings;
+    }
+
+    let swift = match SwiftBin::new(toolchain_path) {
+        Ok(swift) => swift,
+        Err(e) => {
+            findings.push(ValidationFinding::new(
+                FindingKind::InvalidToolchain,
+                Severity::Error,
+                format!("The selected Swift toolchain is invalid: {}", e),
+            ));
+            return findings;
+        }
+    };
+    let package = match SwiftPackageDump::load(&swift, project_path) {
+        Ok(package) => package,
+        Err(e) => {
+            findings.extend(package_errors(&e));
+            return findings;
+        }
+    };
+    check_package(project_path, &package, toml_config.as_ref(), &mut findings);
+
+    // Anything else ProjectConfig rejects, e.g. extension targets or entitlements
+    let missing_executable = findings
+        .iter()
+        .any(|f| f.kind == FindingKind::MissingExecutable);
+    if let Some(toml_config) = toml_config.filter(|_| !missing_executable) {
+        if let Err(e) =
+            ProjectConfig::resolve(project_path.to_path_buf(), toml_config, package, None, None)
+        {
+            let line = config_line(project_path, &e);
+            findings.push(
+                ValidationFinding::new(FindingKind::InvalidConfig, Severity::Error, e)
+                    .at("crosscode.toml", line),
+            );
+        }
+    }
+    findings
+}
+
+fn check_config(project_path: &Path, findings: &mut Vec<ValidationFinding>) -> Option<TomlConfig> {
+    let content = match fs::read_to_string(project_path.join("crosscode.toml")) {
+        Ok(content) => content,
+        Err(_) => {
+            let bundle_id = default_bundle_id(project_path);
+            findings.push(
+                ValidationFinding::new(
+                    FindingKind::MissingConfig,
+                    Severity::Error,
+                    "crosscode.toml is missing",
+                )
+                .with_fix(
+                    format!("Create crosscode.toml with bundle ID {}", bundle_id),
+                    FixAction::CreateConfig { bundle_id },
+                ),
+            );
+            return None;
+        }
+    };
+
+    if let Err(e) = content.parse::<toml::Table>() {
+        let line = e.span().map(|span| line_of(&content, span.start));
+        findings.push(
+            ValidationFinding::new(FindingKind::InvalidConfig, Severity::Error, e.message())
+                .at("crosscode.toml", line),
+        );
+        return None;
+    }
+    let format_version = match read_format_version(&content) {
+        Ok(format_version) => format_version,
+        Err(e) => {
+            findings.push(
+                ValidationFinding::new(FindingKind::InvalidConfig, Severity::Error, e)
+                    .at("crosscode.toml", None),
+            );
+            return None;
+        }
+    };
+    if format_version > FORMAT_VERSION {
+        findings.push(
+            ValidationFinding::new(
+                FindingKind::UnsupportedFormatVersion,
+                Severity::Error,
+                format!(
+                    "crosscode.toml uses format version {}, this version of CrossCode only supports up to {}. You may need to update CrossCode.",
+                    format_version, FORMAT_VERSION
+                ),
+            )
+            .at("crosscode.toml", find_line(&content, "format_version")),
+        );
+        return None;
+    }
+    if format_version < FORMAT_VERSION {
+        findings.push(
+            ValidationFinding::new(
+                FindingKind::OutdatedFormatVersion,
+                Severity::Error,
+                format!(
+                    "crosscode.toml uses format version {}, it needs to be upgraded to {}",
+                    format_version, FORMAT_VERSION
+                ),
+            )
+            .at("crosscode.toml", find_line(&content, "format_version"))
+            .with_fix(
+                format!(
+                    "Upgrade crosscode.toml to version {}, keeping a backup",
+                    FORMAT_VERSION
+                ),
+                FixAction::MigrateConfig,
+            ),
+        );
+        return None;
+    }
+
+    match toml::from_str::<TomlConfig>(&content) {
+        Ok(config) => Some(config),
+        Err(e) => {
+            let line = e.span().map(|span| line_of(&content, span.start));
+            findings.push(
+                ValidationFinding::new(FindingKind::InvalidConfig, Severity::Error, e.message())
+                    .at("crosscode.toml", line),
+            );
+            None
+        }
+    }
+}
+
+fn check_package(
+    project_path: &Path,
+    package: &SwiftPackageDump,
+    toml_config: Option<&TomlConfig>,
+    findings: &mut Vec<ValidationFinding>,
+) {
+    let manifest = fs::read_to_string(project_path.join("Package.swift")).unwrap_or_default();
+    if let Some(toml_config) = toml_config {
+        check_products(project_path, package, toml_config, &manifest, findings);
+    }
+
+    if package.platform_version("ios").is_none() {
+        let version = toml_config
+            .and_then(|c| c.platform.deployment_target.clone())
+            .unwrap_or(DEFAULT_DEPLOYMENT_TARGET.to_string());
+        let literal = ios_version_literal(&version);
+        findings.push(
+            ValidationFinding::new(
+                FindingKind::MissingPlatform,
+                Severity::Warning,
+                "Package.swift does not declare an iOS platform, SwiftPM and SourceKit-LSP fall back to the oldest iOS version they support and may reject newer APIs",
+            )
+            .at(
+                "Package.swift",
+                find_line(&manifest, "platforms:").or(find_line(&manifest, "Package(")),
+            )
+            .with_fix(
+                format!("Add .iOS({}) to the package platforms", literal),
+                FixAction::AddIosPlatform { version },
+            ),
+        );
+    }
+}
+
+fn check_products(
+    project_path: &Path,
+    package: &SwiftPackageDump,
+    toml_config: &TomlConfig,
+    manifest: &str,
+    findings: &mut Vec<ValidationFinding>,
+) {
+    let config = fs::read_to_string(project_path.join("crosscode.toml")).unwrap_or_default();
+    let products = package.executable_products();
+    if let Err(e) = toml_config.main_product(package) {
+        let mut finding = match &toml_config.project.product {
+            Some(product) => {
+                ValidationFinding::new(FindingKind::MissingExecutable, Severity::Error, e).at(
+                    "crosscode.toml",
+                    find_line(&config, &format!("\"{}\"", product)),
+                )
+            }
+            None => ValidationFinding::new(FindingKind::MissingExecutable, Severity::Error, e)
+                .at("Package.swift", find_line(manifest, "targets:")),
+        };
+        // Only a guess when there is nothing else to choose from
+        let candidates: Vec<&String> = products
+            .iter()
+            .filter(|product| !toml_config.apps.iter().any(|app| &app.product == *product))
+            .filter(|product| {
+                !toml_config
+                    .extensions
+                    .iter()
+                    .any(|ext| &ext.target == *product)
+            })
+            .collect();
+        if let [product] = candidates.as_slice() {
+            finding = finding.with_fix(
+                format!("Set the app product to {}", product),
+                FixAction::SetProduct {
+                    product: product.to_string(),
+                },
+            );
+        }
+        findings.push(finding);
+    }
+    for app in &toml_config.apps {
+        if !products.contains(&app.product) {
+            findings.push(
+                ValidationFinding::new(
+                    FindingKind::MissingExecutable,
+                    Severity::Error,
+                    format!(
+                        "Executable product {} of [[apps]] not found in Package.swift",
+                        app.product
+                    ),
+                )
+                .at(
+                    "crosscode.toml",
+                    find_line(&config, &format!("\"{}\"", app.product)),
+                ),
+            );
+        }
+    }
+}
+
+// SwiftPM reports manifest errors like the compiler, file:line:col: error: message
+fn package_errors(error: &str) -> Vec<ValidationFinding> {
+    let located = Regex::new(r"(?m)^(.+?):(\d+):(?:\d+:)? (error|warning): (.*)$").unwrap();
+    let findings: Vec<ValidationFinding> = located
+        .captures_iter(error)
+        .map(|captures| {
+            let file = Path::new(&captures[1])
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or(captures[1].to_string());
+            let severity = if &captures[3] == "error" {
+                Severity::Error
+            } else {
+                Severity::Warning
+            };
+            ValidationFinding::new(FindingKind::InvalidPackage, severity, &captures[4])
+                .at(&file, captures[2].parse().ok())
+        })
+        .collect();
+    if findings.iter().any(|f| f.severity == Severity::Error) {
+        return findings;
+    }
+    vec![ValidationFinding::new(
+        FindingKind::InvalidPackage,
+        Severity::Error,
+        format!("SwiftPM was unable to parse your package. {}", error.trim()),
+    )
+    .at("Package.swift", None)]
+}
+
+pub fn apply_fix(
+    project_path: &Path,
+    toolchain_path: &str,
+    action: FixAction,
+) -> Result<String, String> {
+    match action {
+        FixAction::CreateConfig { bundle_id } => {
+            if project_path.join("crosscode.toml").exists() {
+                return Err("crosscode.toml already exists".to_string());
+            }
+            TomlConfig::default(&bundle_id).save(project_path.to_path_buf())?;
+            Ok(format!(
+                "Created crosscode.toml with bundle ID {}",
+                bundle_id
+            ))
+        }
+        FixAction::MigrateConfig => {
+            let migration = migrate_project_config(project_path, MIGRATIONS, false)?;
+            Ok(match migration.backup_path {
+                Some(backup) => format!(
+                    "Upgraded crosscode.toml to version {}, the original was saved to {}",
+                    migration.to_version, backup
+                ),
+                None => format!(
+                    "crosscode.toml is already at version {}",
+                    migration.to_version
+                ),
+            })
+        }
+        FixAction::SetProduct { product } => {
+            let mut toml_config = TomlConfig::load_or_default(project_path.to_path_buf())?;
+            toml_config.project.product = Some(product.clone());
+            toml_config.save(project_path.to_path_buf())?;
+            Ok(format!("Set the app product to {}", product))
+        }
+        FixAction::AddIosPlatform { version } => {
+            let swift = SwiftBin::new(toolchain_path)?;
+            let original = fs::read_to_string(manifest_path(project_path))
+                .map_err(|e| format!("Failed to read Package.swift: {}", e))?;
+            let literal = ios_version_literal(&version);
+            let edited = add_ios_platform(&original, &literal)?;
+            write_manifest(
+                &swift,
+                project_path,
+                &original,
+                &edited,
+                |package| match package.platform_version("ios") {
+                    Some(_) => Ok(()),
+                    None => Err("The iOS platform was not added to Package.swift".to_string()),
+                },
+            )?;
+            Ok(format!("Added .iOS({}) to Package.swift", literal))
+        }
+    }
+}
+
+// Adds to the package's platforms list, or a new one after its name. Only the top level
+// Package(...) arguments are looked at, targets have platforms: conditions of their own.
+fn add_ios_platform(manifest: &str, literal: &str) -> Result<String, String> {
+    let platform = format!(".iOS({})", literal);
+    let unsupported = || {
+        format!(
+            "Couldn't find where to add the platform, add platforms: [{}] to Package.swift manually",
+            platform
+        )
+    };
+    let mask = code_mask(manifest);
+    let layout = manifest_layout(&mask).map_err(|_| unsupported())?;
+    let mut edited = manifest.to_string();
+
+    if let Some((open, close)) = layout.array_argument(manifest, &mask, "platforms")? {
+        let items = split_items(&mask, open + 1, close);
+        if items
+            .iter()
+            .any(|(s, e)| manifest[*s..*e].starts_with(".iOS"))
+        {
+            return Err("Package.swift already declares an iOS platform".to_string());
+        }
+        match items.first() {
+            Some(&(start, _)) => edited.insert_str(start, &format!("{}, ", platform)),
+            None => edited.insert_str(open + 1, &platform),
+        }
+        return Ok(edited);
+    }
+
+    // SwiftPM wants its arguments in order, platforms come right after the name and default
+    // localization
+    let after = ["defaultLocalization", "name"]
+        .iter()
+        .find_map(|name| layout.argument(manifest, name))
+        .ok_or_else(unsupported)?;
+    let next = layout.arguments.iter().find(|(start, _)| *start > after.1);
+    match next {
+        Some(&(start, _)) if starts_line(manifest, start) => edited.insert_str(
+            line_start(manifest, start),
+            &format!(
+                "{}platforms: [{}],\n",
+                indentation(manifest, start),
+                platform
+            ),
+        ),
+        Some(&(start, _)) => edited.insert_str(start, &format!("platforms: [{}], ", platform)),
+        None => edited.insert_str(after.1, &format!(", platforms: [{}]", platform)),
+    }
+    Ok(edited)
+}
+
+// Whole versions have a case in PackageDescription, e.g. .v15, others need a string
+fn ios_version_literal(version: &str) -> String {
+    let parts: Vec<&str> = version.trim().split('.').collect();
+    if parts[0].parse::<u32>().is_ok() && parts[1..].iter().all(|p| *p == "0") {
+        format!(".v{}", parts[0])
+    } else {
+        format!("\"{}\"", version.trim())
+    }
+}
+
+// A lowercased bundle ID component from the folder name
+fn default_bundle_id(project_path: &Path) -> String {
+    let name: String = project_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect();
+    format!(
+        "com.example.{}",
+        if name.is_empty() { "myapp" } else { &name }
+    )
+}
+
+// Errors from ProjectConfig quote the offending value, which is usually enough to find it
+fn config_line(project_path: &Path, error: &str) -> Option<usize> {
+    let content = fs::read_to_string(project_path.join("crosscode.toml")).ok()?;
+    Regex::new(r#"(?:"([^"]+)")|(?:target (\S+) )"#)
+        .unwrap()
+        .captures_iter(error)
+        .filter_map(|c| c.get(1).or(c.get(2)))
+        .find_map(|value| find_line(&content, value.as_str()))
+}
+
+fn find_line(content: &str, needle: &str) -> Option<usize> {
+    content
+        .lines()
+        .position(|line| line.contains(needle))
+        .map(|index| index + 1)
+}
+
+fn line_of(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+#[tauri::command]
+pub fn apply_validation_fix(
+    project_path: String,
+    toolchain_path: String,
+    action: FixAction,
+) -> Result<String, String> {
+    apply_fix(&PathBuf::from(project_path), &toolchain_path, action)
+}
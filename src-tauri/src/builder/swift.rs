@@ -1,13 +1,18 @@
 #[cfg(target_os = "windows")]
 use crate::windows::has_wsl;
+#[cfg(not(target_os = "windows"))]
+use std::os::unix::process::CommandExt;
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 
 use crate::{
     builder::{
-        config::{BuildSettings, ProjectConfig},
+        config::{BuildRequest, BuildSettings, ProjectConfig, TargetPlatform},
         crossplatform::{linux_env, windows_path},
+        diagnostics::{DiagnosticCollector, DiagnosticParser},
+        jobs::{BuildJobs, JobProcess, PROCESS_GROUP_PREFIX},
         packer::{pack, zip_ipa},
+        version::{next_build_number, save_build_number},
     },
     emit_error_and_return,
     sideloader::{device::DeviceInfo, sideload::sideload_app},
@@ -19,7 +24,7 @@ use std::{
     process::{Command, Output, Stdio},
     thread,
 };
-use tauri::{Emitter, Window};
+use tauri::{Emitter, Manager, State, Window};
 use tokio::process::Command as TokioCommand;
 
 #[cfg(target_os = "windows")]
@@ -143,6 +148,91 @@ impl SwiftBin {
         }
     }
 
+    // Other tools shipped in the toolchain's usr/bin, like dsymutil. Falls back to the one
+    // on PATH when the toolchain's can't be found.
+    pub fn tool_command(&self, tool: &str) -> Command {
+        let tool_path = self.toolchain_tool(tool).unwrap_or(tool.to_string());
+        #[cfg(target_os = "windows")]
+        {
+            let mut cmd = Command::new("wsl");
+            cmd.arg(tool_path);
+            cmd.creation_flags(CREATE_NO_WINDOW);
+            cmd
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            Command::new(tool_path)
+        }
+    }
+
+    // bin_path may be a symlink or a swiftly shim that lives outside the toolchain, so it
+    // is resolved first, then the compiler is asked where its resources are
+    fn toolchain_tool(&self, tool: &str) -> Option<String> {
+        #[cfg(not(target_os = "windows"))]
+        if let Some(path) = std::fs::canonicalize(&self.bin_path)
+            .ok()
+            .and_then(|swift| Some(swift.parent()?.join(tool)))
+            .filter(|path| path.is_file())
+        {
+            return Some(path.to_string_lossy().to_string());
+        }
+
+        let output = self.output(&["-print-target-info"]).ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let info: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+        // <toolchain>/usr/lib/swift, kept as a string since on Windows it's a path inside WSL
+        let resources = info["paths"]["runtimeResourcePath"].as_str()?;
+        let usr = resources.trim_end_matches('/').rsplitn(3, '/').nth(2)?;
+        let tool_path = format!("{}/bin/{}", usr, tool);
+        #[cfg(not(target_os = "windows"))]
+        if !PathBuf::from(&tool_path).is_file() {
+            return None;
+        }
+        Some(tool_path)
+    }
+
+    // Where the installed Darwin SDK keeps the .tbd stubs and headers for a triple
+    pub fn sdk_root(&self, triple: &str) -> Result<PathBuf, String> {
+        let output = self
+            .output(&["sdk", "configure", "--show-configuration", "darwin", triple])
+            .map_err(|e| format!("Failed to execute swift command: {}", e))?;
+        if !output.status.success() {
+            return Err("The Darwin SDK is not installed".to_string());
+        }
+        let path = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find_map(|line| line.strip_prefix("sdkRootPath:"))
+            .map(|path| path.trim().to_string())
+            .ok_or("The Darwin SDK configuration has no sdkRootPath".to_string())?;
+        Ok(PathBuf::from(windows_path(&path)?))
+    }
+
+    // For long running builds, on Windows the process is wrapped so it runs in its own
+    // session inside WSL and reports its process group, which lets the build be cancelled
+    pub fn build_command(&self) -> Command {
+        #[cfg(target_os = "windows")]
+        {
+            let mut cmd = Command::new("wsl");
+            cmd.args([
+                "--exec",
+                "setsid",
+                "-w",
+                "sh",
+                "-c",
+                &format!("echo {}$$; exec \"$0\" \"$@\"", PROCESS_GROUP_PREFIX),
+            ])
+            .arg(&self.bin_path);
+            cmd.creation_flags(CREATE_NO_WINDOW);
+            cmd
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            self.command()
+        }
+    }
+
     pub fn sourcekit_command(&self) -> TokioCommand {
         #[cfg(target_os = "windows")]
         {
@@ -359,21 +449,76 @@ fn get_swiftly_path() -> Option<String> {
     None
 }
 
-async fn build_swift_internal(
+pub struct PackedApp {
+    pub path: PathBuf,
+    pub config: ProjectConfig,
+    // The next build number is already in the packed Info.plist, but only written to
+    // crosscode.toml once nothing else can fail, so failed builds don't use one up
+    increment_build_number: bool,
+}
+
+impl PackedApp {
+    pub fn commit_build_number(&self, window: &Window) -> Result<(), String> {
+        if !self.increment_build_number {
+            return Ok(());
+        }
+        match save_build_number(self.config.project_path.clone(), &self.config.version_num) {
+            Ok(()) => {
+                window
+                    .emit(
+                        "build-output",
+                        format!("Build number is now {}", self.config.version_num),
+                    )
+                    .ok();
+                Ok(())
+            }
+            Err(e) => {
+                emit_error_and_return(window, &format!("Failed to increment build number: {}", e))
+            }
+        }
+    }
+}
+
+pub async fn build_swift_internal(
     window: &Window,
     folder: &str,
     toolchain_path: &str,
-    build_settings: BuildSettings,
+    mut build_settings: BuildSettings,
     emit_exit_code: bool,
-) -> Result<(PathBuf, ProjectConfig), String> {
-    let config = match ProjectConfig::load(PathBuf::from(&folder), &toolchain_path) {
+    job: &JobProcess,
+) -> Result<PackedApp, String> {
+    let mut config = match ProjectConfig::load(
+        PathBuf::from(&folder),
+        &toolchain_path,
+        build_settings.configuration.as_deref(),
+        build_settings.app.as_deref(),
+    ) {
         Ok(config) => config,
         Err(e) => {
             return emit_error_and_return(&window, &format!("Failed to load project config: {}", e))
         }
     };
+    if let Some(debug) = config.debug {
+        build_settings.debug = debug;
+    }
+    if let Err(e) = build_settings.validate() {
+        return emit_error_and_return(&window, &e);
+    }
+    let increment_build_number = !build_settings.debug && config.packaging.increment_build_number;
+    if increment_build_number {
+        config.version_num = match next_build_number(&config.version_num) {
+            Ok(version_num) => version_num,
+            Err(e) => {
+                return emit_error_and_return(
+                    window,
+                    &format!("Failed to increment build number: {}", e),
+                )
+            }
+        };
+    }
+    let (app_rpath, extension_rpath) = build_settings.platform.framework_rpaths();
     let swift_bin = SwiftBin::new(&toolchain_path)?;
-    let mut cmd = swift_bin.command();
+    let mut cmd = swift_bin.build_command();
     cmd.arg("build")
         .arg("-c")
         .arg(if build_settings.debug {
@@ -382,18 +527,50 @@ async fn build_swift_internal(
             "release"
         })
         .arg("--swift-sdk")
-        .arg("arm64-apple-ios")
+        .arg(build_settings.sdk_triple())
+        .arg("-Xswiftc")
+        .arg("-target")
+        .arg("-Xswiftc")
+        .arg(config.target_triple(&build_settings))
+        // The one-line-per-location style that DiagnosticParser understands
+        .args(["-Xswiftc", "-diagnostic-style", "-Xswiftc", "llvm"])
+        // Where embedded frameworks live relative to the app and extension executables
+        .args(["-Xlinker", "-rpath", "-Xlinker", app_rpath])
+        .args(["-Xlinker", "-rpath", "-Xlinker", extension_rpath])
         .current_dir(&folder);
+    for condition in &config.build_flags.swift_conditions {
+        cmd.arg("-Xswiftc").arg(format!("-D{}", condition));
+    }
+    for flag in &config.build_flags.swiftc_flags {
+        cmd.arg("-Xswiftc").arg(flag);
+    }
+    for flag in &config.build_flags.linker_flags {
+        cmd.arg("-Xlinker").arg(flag);
+    }
 
-    pipe_command(&mut cmd, &window, emit_exit_code).await?;
-
-    match pack(PathBuf::from(&folder), &config, &build_settings) {
+    pipe_command(&mut cmd, &window, emit_exit_code, Some(job)).await?;
+
+    match pack(
+        PathBuf::from(&folder),
+        &config,
+        &build_settings,
+        &swift_bin,
+        job,
+        |line| {
+            window.emit("build-output", line).ok();
+        },
+    ) {
         Ok(app) => {
             window
                 .emit("build-output", "Pack Success")
                 .expect("failed to send output");
-            Ok((app, config))
+            Ok(PackedApp {
+                path: app,
+                config,
+                increment_build_number,
+            })
         }
+        Err(e) if job.is_cancelled() => emit_error_and_return(window, &e),
         Err(e) => emit_error_and_return(&window, &format!("Failed to pack app: {}", e)),
     }
 }
@@ -401,19 +578,48 @@ async fn build_swift_internal(
 #[tauri::command]
 pub async fn build_swift(
     window: tauri::Window,
+    jobs: State<'_, BuildJobs>,
     folder: String,
     toolchain_path: String,
-    debug: bool,
+    build: BuildRequest,
 ) -> Result<(), String> {
-    let build_settings = BuildSettings { debug };
+    let build_settings = BuildSettings::from(build);
+    let platform = build_settings.platform;
     if !validate_toolchain(&toolchain_path) {
         return emit_error_and_return(&window, "Invalid Toolchain");
     }
+    let job = match jobs.start(&window, &folder, "build") {
+        Ok(job) => job,
+        Err(e) => return emit_error_and_return(&window, &e),
+    };
 
-    let (app, config) =
-        build_swift_internal(&window, &folder, &toolchain_path, build_settings, true).await?;
+    let packed = build_swift_internal(
+        &window,
+        &folder,
+        &toolchain_path,
+        build_settings,
+        true,
+        job.process(),
+    )
+    .await?;
+
+    // Simulator and macOS apps are run as a bundle, there is no IPA for them
+    if platform != TargetPlatform::Ios {
+        packed.commit_build_number(&window)?;
+        window
+            .emit(
+                "build-output",
+                format!("Build Success, output app at {}", packed.path.display()),
+            )
+            .expect("failed to send output");
+        job.succeed();
+        return Ok(());
+    }
 
-    let ipa_path = zip_ipa(app, &config);
+    if let Err(e) = job.check_cancelled() {
+        return emit_error_and_return(&window, &e);
+    }
+    let ipa_path = zip_ipa(packed.path.clone(), &packed.config);
     if ipa_path.is_err() {
         return emit_error_and_return(
             &window,
@@ -421,6 +627,7 @@ pub async fn build_swift(
         );
     }
     let ipa_path = ipa_path.unwrap();
+    packed.commit_build_number(&window)?;
 
     window
         .emit(
@@ -429,52 +636,88 @@ pub async fn build_swift(
         )
         .expect("failed to send output");
 
+    job.succeed();
     Ok(())
 }
 
 #[tauri::command]
 pub async fn clean_swift(
     window: tauri::Window,
+    jobs: State<'_, BuildJobs>,
     folder: String,
     toolchain_path: String,
 ) -> Result<(), String> {
+    let job = match jobs.start(&window, &folder, "clean") {
+        Ok(job) => job,
+        Err(e) => return emit_error_and_return(&window, &e),
+    };
     let swift_bin = SwiftBin::new(&toolchain_path)?;
-    let mut cmd = swift_bin.command();
+    let mut cmd = swift_bin.build_command();
     cmd.arg("package").arg("clean").current_dir(folder);
 
     window
         .emit("build-output", "Cleaning...")
         .expect("failed to send output");
 
-    pipe_command(&mut cmd, &window, true).await
+    pipe_command(&mut cmd, &window, true, Some(job.process())).await?;
+    job.succeed();
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn deploy_swift(
-    handle: tauri::AppHandle,
     window: tauri::Window,
+    jobs: State<'_, BuildJobs>,
     anisette_server: String,
     device: DeviceInfo,
     folder: String,
     toolchain_path: String,
-    debug: bool,
+    build: BuildRequest,
 ) -> Result<(), String> {
-    let build_settings = BuildSettings { debug };
+    // Sideloading is for devices only
+    let build_settings = BuildSettings::from(build);
+    if build_settings.platform != TargetPlatform::Ios {
+        return emit_error_and_return(&window, "Only iOS device builds can be installed");
+    }
     if !validate_toolchain(&toolchain_path) {
         return emit_error_and_return(&window, "Invalid Toolchain");
     }
+    let job = match jobs.start(&window, &folder, "deploy") {
+        Ok(job) => job,
+        Err(e) => return emit_error_and_return(&window, &e),
+    };
 
-    let (app, _) =
-        build_swift_internal(&window, &folder, &toolchain_path, build_settings, false).await?;
+    let packed = build_swift_internal(
+        &window,
+        &folder,
+        &toolchain_path,
+        build_settings,
+        false,
+        job.process(),
+    )
+    .await?;
+    // Installing can't be interrupted, so this is the last point a cancel takes effect
+    if let Err(e) = job.check_cancelled() {
+        return emit_error_and_return(&window, &e);
+    }
 
-    sideload_app(&handle, &window, anisette_server, device, app)
-        .await
-        .map_err(|e| format!("Failed to sideload app: {}", e))?;
+    sideload_app(
+        window.app_handle(),
+        &window,
+        anisette_server,
+        device,
+        packed.path.clone(),
+        &packed.config.bundle_entitlements(&packed.path),
+    )
+    .await
+    .map_err(|e| format!("Failed to sideload app: {}", e))?;
+    packed.commit_build_number(&window)?;
 
     window
         .emit("build-output", "Build & Install Success")
         .expect("failed to send output");
 
+    job.succeed();
     Ok(())
 }
 
@@ -482,10 +725,18 @@ pub async fn pipe_command(
     cmd: &mut Command,
     window: &tauri::Window,
     emit_exit_code: bool,
+    job: Option<&JobProcess>,
 ) -> Result<(), String> {
     let name = "build-output";
     cmd.stdout(Stdio::piped());
     cmd.stderr(Stdio::piped());
+    if let Some(job) = job {
+        if job.is_cancelled() {
+            return emit_error_and_return(&window, "Build cancelled");
+        }
+        #[cfg(not(target_os = "windows"))]
+        cmd.process_group(0);
+    }
 
     let mut command = match cmd.spawn() {
         Ok(cmd) => cmd,
@@ -493,6 +744,11 @@ pub async fn pipe_command(
             return emit_error_and_return(&window, "Failed to spawn build command");
         }
     };
+    // On Windows the group is only known once the WSL wrapper prints it
+    #[cfg(not(target_os = "windows"))]
+    if let Some(job) = job {
+        job.set_process_group(command.id());
+    }
 
     let stdout = match command.stdout.take() {
         Some(out) => out,
@@ -508,8 +764,21 @@ pub async fn pipe_command(
         }
     };
 
-    let stdout_handle = spawn_output_thread(stdout, window.clone(), name.to_string());
-    let stderr_handle = spawn_output_thread(stderr, window.clone(), name.to_string());
+    let diagnostics = DiagnosticCollector::new(window.clone());
+    let stdout_handle = spawn_output_thread(
+        stdout,
+        window.clone(),
+        name.to_string(),
+        diagnostics.clone(),
+        job.cloned(),
+    );
+    let stderr_handle = spawn_output_thread(
+        stderr,
+        window.clone(),
+        name.to_string(),
+        diagnostics.clone(),
+        None,
+    );
 
     stdout_handle.join().expect("stdout thread panicked");
     stderr_handle.join().expect("stderr thread panicked");
@@ -522,6 +791,13 @@ pub async fn pipe_command(
     };
 
     let exit_code = exit_status.code().unwrap_or(1);
+    diagnostics.finish(exit_code);
+    if let Some(job) = job {
+        job.clear_process_group();
+        if job.is_cancelled() {
+            return emit_error_and_return(&window, "Build cancelled");
+        }
+    }
 
     if exit_code != 0 || emit_exit_code {
         window
@@ -540,12 +816,25 @@ fn spawn_output_thread<R: std::io::Read + Send + 'static>(
     reader: R,
     window: tauri::Window,
     name: String,
+    diagnostics: DiagnosticCollector,
+    job: Option<JobProcess>,
 ) -> std::thread::JoinHandle<()> {
     thread::spawn(move || {
         let reader = BufReader::new(reader);
+        let mut parser = DiagnosticParser::new(diagnostics);
         for line in reader.lines() {
             match line {
                 Ok(line) => {
+                    if let Some(job) = &job {
+                        let process_group = line
+                            .strip_prefix(PROCESS_GROUP_PREFIX)
+                            .and_then(|pgid| pgid.trim().parse().ok());
+                        if let Some(process_group) = process_group {
+                            job.set_process_group(process_group);
+                            continue;
+                        }
+                    }
+                    parser.feed(&line);
                     window.emit(&name, line).expect("failed to send output");
                 }
                 Err(err) => {
@@ -553,9 +842,10 @@ fn spawn_output_thread<R: std::io::Read + Send + 'static>(
                         .emit(&name, "command.done.999".to_string())
                         .expect("failed to send output");
                     eprintln!("Error reading output: {}", err);
-                    return;
+                    break;
                 }
             }
         }
+        parser.flush();
     })
 }
@@ -0,0 +1,181 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Output, Stdio},
+};
+
+use dircpy::CopyBuilder;
+
+use crate::builder::{crossplatform::linux_path, macho::read_macho, swift::SwiftBin};
+
+pub fn dsyms_dir(project_path: &Path) -> PathBuf {
+    project_path.join(".crosscode").join("dSYMs")
+}
+
+// The debug info of a linked binary stays in the object files, dsymutil collects it into a
+// .dSYM bundle. Crash reports only identify binaries by UUID, so that is what they are
+// stored under.
+pub fn archive_dsym(
+    swift_bin: &SwiftBin,
+    project_path: &Path,
+    executable: &Path,
+) -> Result<PathBuf, String> {
+    let name = executable
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let uuid = read_macho(executable)?
+        .into_iter()
+        .find_map(|slice| slice.uuid)
+        .ok_or(format!("{} has no UUID", name))?;
+
+    let dest_dir = dsyms_dir(project_path).join(&uuid);
+    let dest = dest_dir.join(format!("{}.dSYM", name));
+    // A rebuild with no changes produces the same UUID
+    if dest.exists() {
+        fs::remove_dir_all(&dest)
+            .map_err(|e| format!("Failed to remove old {}: {}", dest.display(), e))?;
+    }
+    fs::create_dir_all(&dest_dir)
+        .map_err(|e| format!("Failed to create {}: {}", dest_dir.display(), e))?;
+
+    let output = swift_bin
+        .tool_command("dsymutil")
+        .arg(tool_path(executable)?)
+        .arg("-o")
+        .arg(tool_path(&dest)?)
+        .output();
+    check_output("dsymutil", output)?;
+    Ok(dest)
+}
+
+// Copies a dSYM into dir, e.g. beside the IPA so they can be handed out together
+pub fn copy_dsym(dsym: &Path, dir: &Path) -> Result<PathBuf, String> {
+    let name = dsym
+        .file_name()
+        .ok_or(format!("{} is not a dSYM", dsym.display()))?;
+    let dest = dir.join(name);
+    if dest.exists() {
+        if fs::canonicalize(&dest).ok() == fs::canonicalize(dsym).ok() {
+            return Ok(dest);
+        }
+        fs::remove_dir_all(&dest)
+            .map_err(|e| format!("Failed to remove old {}: {}", dest.display(), e))?;
+    }
+    CopyBuilder::new(dsym, &dest)
+        .overwrite(true)
+        .run()
+        .map_err(|e| format!("Failed to copy dSYM to {}: {}", dest.display(), e))?;
+    Ok(dest)
+}
+
+pub fn dsyms_in(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path.is_dir() && path.extension().is_some_and(|ext| ext == "dSYM"))
+        .collect()
+}
+
+// Only after the dSYM was made, the symbols are gone afterwards
+pub fn strip_executable(swift_bin: &SwiftBin, executable: &Path) -> Result<(), String> {
+    let output = swift_bin
+        .tool_command("llvm-strip")
+        .arg("--strip-all")
+        .arg(tool_path(executable)?)
+        .output();
+    check_output("llvm-strip", output)?;
+    Ok(())
+}
+
+// A dSYM or unstripped build product with the given UUID, dSYMs first since release
+// executables in the app are stripped
+pub fn find_local_binary(project_path: &Path, uuid: &str) -> Option<PathBuf> {
+    let uuid = uuid.to_uppercase();
+    let dsym_binaries = fs::read_dir(dsyms_dir(project_path).join(&uuid))
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .flat_map(|dsym| {
+            fs::read_dir(dsym.path().join("Contents").join("Resources").join("DWARF"))
+                .into_iter()
+                .flatten()
+                .filter_map(|e| e.ok())
+        })
+        .map(|e| e.path());
+    let build_products = fs::read_dir(project_path.join(".build"))
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .flat_map(|triple| {
+            ["debug", "release"]
+                .into_iter()
+                .map(move |configuration| triple.path().join(configuration))
+        })
+        .flat_map(|dir| {
+            fs::read_dir(dir)
+                .into_iter()
+                .flatten()
+                .filter_map(|e| e.ok())
+        })
+        .map(|e| e.path())
+        .filter(|path| path.is_file() && path.extension().is_none_or(|ext| ext == "dylib"));
+
+    dsym_binaries.chain(build_products).find(|path| {
+        read_macho(path).is_ok_and(|slices| {
+            slices
+                .iter()
+                .any(|slice| slice.uuid.as_deref() == Some(uuid.as_str()))
+        })
+    })
+}
+
+// Turns mangled Swift names into readable ones, anything swift demangle doesn't recognise
+// comes back unchanged
+pub fn demangle(swift_bin: &SwiftBin, names: &[String]) -> Result<Vec<String>, String> {
+    if names.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut child = swift_bin
+        .command()
+        .args(["demangle", "--simplified"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run swift demangle: {}", e))?;
+    let input = names.join("\n") + "\n";
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output();
+    writer.join().ok();
+    let output = check_output("swift demangle", output)?;
+    let demangled: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|l| l.to_string())
+        .collect();
+    if demangled.len() != names.len() {
+        return Err("swift demangle returned an unexpected number of names".to_string());
+    }
+    Ok(demangled)
+}
+
+// The tools run inside WSL on Windows
+fn tool_path(path: &Path) -> Result<String, String> {
+    linux_path(&path.to_string_lossy())
+}
+
+fn check_output(tool: &str, output: std::io::Result<Output>) -> Result<Output, String> {
+    let output = output.map_err(|e| format!("Failed to run {}: {}", tool, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            tool,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output)
+}
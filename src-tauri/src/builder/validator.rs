@@ -0,0 +1,437 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use plist::{Dictionary, Value};
+use serde::Serialize;
+
+use crate::builder::{
+    config::TargetPlatform,
+    diagnostics::Severity,
+    macho::{read_macho, MH_DYLIB, MH_EXECUTE},
+};
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleIssue {
+    pub severity: Severity,
+    // Relative to the .app
+    pub file: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for BundleIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "Error",
+            _ => "Warning",
+        };
+        match &self.file {
+            Some(file) => write!(f, "{}: {}: {}", severity, file, self.message),
+            None => write!(f, "{}: {}", severity, self.message),
+        }
+    }
+}
+
+struct Validator<'a> {
+    app_path: &'a Path,
+    platform: TargetPlatform,
+    issues: Vec<BundleIssue>,
+}
+
+impl Validator<'_> {
+    fn error(&mut self, file: Option<&Path>, message: String) {
+        self.push(Severity::Error, file, message);
+    }
+
+    fn warning(&mut self, file: Option<&Path>, message: String) {
+        self.push(Severity::Warning, file, message);
+    }
+
+    fn push(&mut self, severity: Severity, file: Option<&Path>, message: String) {
+        let file = file.map(|f| {
+            f.strip_prefix(self.app_path)
+                .unwrap_or(f)
+                .to_string_lossy()
+                .replace('\\', "/")
+        });
+        self.issues.push(BundleIssue {
+            severity,
+            file,
+            message,
+        });
+    }
+}
+
+// Catches the mistakes that otherwise only show up as a vague installation failure on the
+// device. Checks the app, its extensions and embedded frameworks.
+pub fn validate_bundle(app_path: &Path, platform: TargetPlatform) -> Vec<BundleIssue> {
+    let mut validator = Validator {
+        app_path,
+        platform,
+        issues: Vec::new(),
+    };
+
+    let app_id = validate_bundle_dir(&mut validator, app_path, MH_EXECUTE);
+    let contents = contents_dir(app_path, platform);
+
+    let plugins = contents.join("PlugIns");
+    for extension in bundles_in(&plugins, "appex") {
+        let extension_id = validate_bundle_dir(&mut validator, &extension, MH_EXECUTE);
+        if let (Some(app_id), Some(extension_id)) = (&app_id, extension_id) {
+            if !extension_id.starts_with(&format!("{}.", app_id)) {
+                validator.error(
+                    Some(&extension),
+                    format!(
+                        "Extension bundle ID {} must start with the app's bundle ID {}",
+                        extension_id, app_id
+                    ),
+                );
+            }
+        }
+    }
+
+    // macOS frameworks keep their Info.plist under Versions/, only the flat layout is checked
+    let frameworks = contents.join("Frameworks");
+    if platform != TargetPlatform::Macos {
+        for framework in bundles_in(&frameworks, "framework") {
+            validate_bundle_dir(&mut validator, &framework, MH_DYLIB);
+        }
+    }
+    for dylib in files_in(&frameworks, "dylib") {
+        validate_binary(&mut validator, &dylib, MH_DYLIB);
+    }
+
+    validator.issues
+}
+
+// Guesses the platform of a bundle that wasn't built by this session
+pub fn detect_platform(app_path: &Path) -> TargetPlatform {
+    if app_path.join("Contents").join("Info.plist").exists() {
+        return TargetPlatform::Macos;
+    }
+    let simulator = read_info_plist(app_path, TargetPlatform::Ios)
+        .ok()
+        .and_then(|info| {
+            info.get("CFBundleSupportedPlatforms")
+                .and_then(|p| p.as_array())
+                .map(|platforms| {
+                    platforms
+                        .iter()
+                        .any(|p| p.as_string() == Some(TargetPlatform::IosSimulator.plist_name()))
+                })
+        })
+        .unwrap_or(false);
+    if simulator {
+        TargetPlatform::IosSimulator
+    } else {
+        TargetPlatform::Ios
+    }
+}
+
+// Returns the bundle ID if the bundle has a usable one
+fn validate_bundle_dir(validator: &mut Validator, bundle: &Path, file_type: u32) -> Option<String> {
+    let platform = validator.platform;
+    let contents = contents_dir(bundle, platform);
+    let plist_path = contents.join("Info.plist");
+    let info = match read_info_plist(bundle, platform) {
+        Ok(info) => info,
+        Err(e) => {
+            validator.error(Some(&plist_path), e);
+            return None;
+        }
+    };
+
+    let bundle_id = match info.get("CFBundleIdentifier").and_then(|v| v.as_string()) {
+        Some(id) => {
+            if let Err(e) = check_bundle_id(id) {
+                validator.error(Some(&plist_path), e);
+            }
+            Some(id.to_string())
+        }
+        None => {
+            validator.error(
+                Some(&plist_path),
+                "CFBundleIdentifier is missing".to_string(),
+            );
+            None
+        }
+    };
+
+    if platform == TargetPlatform::Ios
+        && file_type == MH_EXECUTE
+        && !info.contains_key("MinimumOSVersion")
+    {
+        validator.warning(
+            Some(&plist_path),
+            "MinimumOSVersion is missing, iOS will assume the app runs on any version".to_string(),
+        );
+    }
+
+    let executable_name = match info.get("CFBundleExecutable").and_then(|v| v.as_string()) {
+        Some(name) => name.to_string(),
+        None => {
+            validator.error(
+                Some(&plist_path),
+                "CFBundleExecutable is missing".to_string(),
+            );
+            return bundle_id;
+        }
+    };
+    let executables = if platform == TargetPlatform::Macos {
+        contents.join("MacOS")
+    } else {
+        contents.clone()
+    };
+    let executable = executables.join(&executable_name);
+    if executable.is_dir() {
+        validator.error(
+            Some(&executable),
+            format!(
+                "{} is a directory, a resource is using the executable's name",
+                executable_name
+            ),
+        );
+    } else if !executable.exists() {
+        validator.error(
+            Some(&executable),
+            format!(
+                "CFBundleExecutable is {}, but there is no such file",
+                executable_name
+            ),
+        );
+    } else {
+        validate_binary(validator, &executable, file_type);
+    }
+    // Devices and Windows filesystems ignore case, so these overwrite each other
+    for entry in fs::read_dir(&executables)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+    {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name != executable_name && name.eq_ignore_ascii_case(&executable_name) {
+            validator.error(
+                Some(&entry.path()),
+                format!(
+                    "{} only differs from the executable {} by case",
+                    name, executable_name
+                ),
+            );
+        }
+    }
+
+    if file_type == MH_EXECUTE {
+        let resources = if platform == TargetPlatform::Macos {
+            contents.join("Resources")
+        } else {
+            contents.clone()
+        };
+        for icon in icon_files(&info) {
+            if !icon_exists(&resources, &icon) {
+                validator.error(
+                    Some(&plist_path),
+                    format!(
+                        "The icon {} is listed in CFBundleIconFiles but missing",
+                        icon
+                    ),
+                );
+            }
+        }
+    }
+
+    bundle_id
+}
+
+fn validate_binary(validator: &mut Validator, path: &Path, file_type: u32) {
+    let slices = match read_macho(path) {
+        Ok(slices) => slices,
+        Err(e) => {
+            validator.error(Some(path), e);
+            return;
+        }
+    };
+
+    let allowed: &[&str] = match validator.platform {
+        TargetPlatform::Ios => &["arm64", "arm64e"],
+        _ => &["arm64", "x86_64"],
+    };
+    let archs: Vec<&str> = slices.iter().map(|s| s.arch()).collect();
+    if !archs.iter().any(|arch| allowed.contains(arch)) {
+        validator.error(
+            Some(path),
+            format!(
+                "Built for {}, but {} needs {}",
+                archs.join(", "),
+                platform_name(validator.platform),
+                allowed.join(" or ")
+            ),
+        );
+    }
+
+    if slices.iter().any(|s| s.file_type != file_type) {
+        let expected = if file_type == MH_EXECUTE {
+            "an executable"
+        } else {
+            "a dynamic library"
+        };
+        validator.error(Some(path), format!("Not {}", expected));
+    }
+
+    // Everything else is resolved against the rpaths, which pack already checks
+    for dylib in slices.iter().flat_map(|s| s.dylibs.iter()) {
+        let relative = match dylib.strip_prefix("@rpath/") {
+            Some(relative) => relative,
+            None => continue,
+        };
+        if relative.starts_with("libswift") {
+            continue;
+        }
+        let frameworks = contents_dir(validator.app_path, validator.platform).join("Frameworks");
+        if !frameworks.join(relative).exists() {
+            validator.error(
+                Some(path),
+                format!("Links {}, which is not embedded in Frameworks", dylib),
+            );
+        }
+    }
+}
+
+// The rules the installer enforces: letters, digits, hyphens and periods, with no empty parts
+fn check_bundle_id(bundle_id: &str) -> Result<(), String> {
+    if let Some(c) = bundle_id
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && *c != '-' && *c != '.')
+    {
+        return Err(format!(
+            "Bundle ID {} contains '{}', only letters, digits, hyphens and periods are allowed",
+            bundle_id, c
+        ));
+    }
+    if bundle_id.split('.').any(|part| part.is_empty()) {
+        return Err(format!(
+            "Bundle ID {} has an empty component, check for leading, trailing or double periods",
+            bundle_id
+        ));
+    }
+    Ok(())
+}
+
+fn read_info_plist(bundle: &Path, platform: TargetPlatform) -> Result<Dictionary, String> {
+    let path = contents_dir(bundle, platform).join("Info.plist");
+    if !path.exists() {
+        return Err("Info.plist is missing".to_string());
+    }
+    Value::from_file(&path)
+        .map_err(|e| format!("Failed to parse Info.plist: {}", e))?
+        .into_dictionary()
+        .ok_or("Info.plist is not a dictionary".to_string())
+}
+
+fn contents_dir(bundle: &Path, platform: TargetPlatform) -> PathBuf {
+    if platform == TargetPlatform::Macos {
+        bundle.join("Contents")
+    } else {
+        bundle.to_path_buf()
+    }
+}
+
+fn icon_files(info: &Dictionary) -> Vec<String> {
+    let mut lists: Vec<&Value> = info.get("CFBundleIconFiles").into_iter().collect();
+    for key in ["CFBundleIcons", "CFBundleIcons~ipad"] {
+        let icons = match info.get(key).and_then(|v| v.as_dictionary()) {
+            Some(icons) => icons,
+            None => continue,
+        };
+        let primary = icons.get("CFBundlePrimaryIcon").into_iter();
+        let alternates = icons
+            .get("CFBundleAlternateIcons")
+            .and_then(|v| v.as_dictionary())
+            .into_iter()
+            .flat_map(|a| a.values());
+        for icon in primary.chain(alternates) {
+            if let Some(files) = icon
+                .as_dictionary()
+                .and_then(|d| d.get("CFBundleIconFiles"))
+            {
+                lists.push(files);
+            }
+        }
+    }
+
+    let mut files: Vec<String> = Vec::new();
+    for name in lists
+        .into_iter()
+        .filter_map(|l| l.as_array())
+        .flatten()
+        .filter_map(|v| v.as_string())
+    {
+        if !files.iter().any(|f| f == name) {
+            files.push(name.to_string());
+        }
+    }
+    files
+}
+
+// Names are usually listed without the scale and extension, e.g. AppIcon60x60 for
+// AppIcon60x60@2x.png
+fn icon_exists(resources: &Path, icon: &str) -> bool {
+    let base = icon.strip_suffix(".png").unwrap_or(icon);
+    files_in(resources, "png").iter().any(|file| {
+        file.file_stem()
+            .map(|stem| stem.to_string_lossy())
+            .is_some_and(|stem| {
+                stem == base
+                    || stem.starts_with(&format!("{}@", base))
+                    || stem.starts_with(&format!("{}~", base))
+            })
+    })
+}
+
+fn bundles_in(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    entries_in(dir, extension)
+        .into_iter()
+        .filter(|p| p.is_dir())
+        .collect()
+}
+
+fn files_in(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    entries_in(dir, extension)
+        .into_iter()
+        .filter(|p| p.is_file())
+        .collect()
+}
+
+fn entries_in(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == extension))
+        .collect();
+    entries.sort();
+    entries
+}
+
+fn platform_name(platform: TargetPlatform) -> &'static str {
+    match platform {
+        TargetPlatform::Ios => "an iOS device",
+        TargetPlatform::IosSimulator => "the iOS simulator",
+        TargetPlatform::Macos => "macOS",
+    }
+}
+
+#[tauri::command]
+pub fn validate_app_bundle(
+    path: String,
+    platform: Option<TargetPlatform>,
+) -> Result<Vec<BundleIssue>, String> {
+    let app_path = PathBuf::from(&path);
+    if !app_path.is_dir() {
+        return Err(format!("{} is not an app bundle", path));
+    }
+    let platform = platform.unwrap_or_else(|| detect_platform(&app_path));
+    Ok(validate_bundle(&app_path, platform))
+}
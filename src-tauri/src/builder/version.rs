@@ -0,0 +1,239 @@
+use std::{cmp::Ordering, fmt, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::builder::config::TomlConfig;
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum VersionBump {
+    Major,
+    Minor,
+    Patch,
+    PreRelease,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectVersion {
+    pub version_num: String,
+    pub version_string: String,
+}
+
+// version_string, as MAJOR.MINOR.PATCH with an optional -PRERELEASE tag
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre_release: Option<String>,
+}
+
+impl Version {
+    // Minor and patch may be left out like in CFBundleShortVersionString
+    pub fn parse(version: &str) -> Result<Self, String> {
+        let invalid = || {
+            format!(
+                "Invalid version \"{}\", expected MAJOR.MINOR.PATCH with an optional -PRERELEASE",
+                version
+            )
+        };
+        let (numbers, pre_release) = match version.trim().split_once('-') {
+            Some((numbers, pre_release)) => (numbers, Some(pre_release)),
+            None => (version.trim(), None),
+        };
+        let parts = numbers
+            .split('.')
+            .map(|part| part.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()
+            .filter(|parts| (1..=3).contains(&parts.len()))
+            .ok_or_else(invalid)?;
+        if let Some(pre_release) = pre_release {
+            if pre_release.is_empty()
+                || pre_release.split('.').any(|id| {
+                    id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                })
+            {
+                return Err(invalid());
+            }
+        }
+        Ok(Version {
+            major: parts[0],
+            minor: parts.get(1).copied().unwrap_or(0),
+            patch: parts.get(2).copied().unwrap_or(0),
+            pre_release: pre_release.map(|p| p.to_string()),
+        })
+    }
+
+    // Same rules as semver, bumping a pre-release to the version it leads up to releases it,
+    // e.g. 1.3.0-beta.2 with a minor bump becomes 1.3.0
+    pub fn bump(&mut self, bump: VersionBump, label: Option<&str>) {
+        let releasing = self.pre_release.is_some();
+        match bump {
+            VersionBump::Major => {
+                if !(releasing && self.minor == 0 && self.patch == 0) {
+                    self.major += 1;
+                }
+                self.minor = 0;
+                self.patch = 0;
+            }
+            VersionBump::Minor => {
+                if !(releasing && self.patch == 0) {
+                    self.minor += 1;
+                }
+                self.patch = 0;
+            }
+            VersionBump::Patch => {
+                if !releasing {
+                    self.patch += 1;
+                }
+            }
+            VersionBump::PreRelease => {
+                // A released version gets a pre-release of the next patch
+                if !releasing {
+                    self.patch += 1;
+                }
+                self.pre_release = Some(self.next_pre_release(label));
+                return;
+            }
+        }
+        self.pre_release = None;
+    }
+
+    // beta.1 becomes beta.2, a different label starts over at .1
+    fn next_pre_release(&self, label: Option<&str>) -> String {
+        let current = self.pre_release.as_deref();
+        let numbered = current
+            .and_then(|p| p.rsplit_once('.'))
+            .and_then(|(label, number)| Some((label, number.parse::<u64>().ok()?)));
+        let (current_label, number) = match numbered {
+            Some((current_label, number)) => (Some(current_label), number),
+            None => (current, 0),
+        };
+        match (label, current_label) {
+            (None, Some(current_label)) => format!("{}.{}", current_label, number + 1),
+            (Some(label), Some(current_label)) if label == current_label => {
+                format!("{}.{}", label, number + 1)
+            }
+            (label, _) => format!("{}.1", label.unwrap_or("beta")),
+        }
+    }
+}
+
+// Semver precedence, a pre-release comes before the release it leads up to
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (&self.pre_release, &other.pre_release) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => compare_pre_release(a, b),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Numeric identifiers compare as numbers and sort before alphanumeric ones
+fn compare_pre_release(a: &str, b: &str) -> Ordering {
+    let mut a_ids = a.split('.');
+    let mut b_ids = b.split('.');
+    loop {
+        let ordering = match (a_ids.next(), b_ids.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => match (a.parse::<u64>(), b.parse::<u64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => a.cmp(b),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(pre_release) = &self.pre_release {
+            write!(f, "-{}", pre_release)?;
+        }
+        Ok(())
+    }
+}
+
+// version_num is CFBundleVersion, up to three period separated integers of which the last is
+// incremented
+pub fn next_build_number(version_num: &str) -> Result<String, String> {
+    let mut parts = version_num
+        .trim()
+        .split('.')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()
+        .filter(|parts| (1..=3).contains(&parts.len()))
+        .ok_or(format!(
+            "Invalid build number \"{}\", expected up to three period separated integers",
+            version_num
+        ))?;
+    *parts.last_mut().unwrap() += 1;
+    Ok(parts
+        .iter()
+        .map(|part| part.to_string())
+        .collect::<Vec<_>>()
+        .join("."))
+}
+
+pub fn bump_project_version(
+    project_path: PathBuf,
+    build: bool,
+    bump: Option<VersionBump>,
+    pre_release: Option<&str>,
+) -> Result<ProjectVersion, String> {
+    let mut config = TomlConfig::load_or_default(project_path.clone())?;
+    if build {
+        config.project.version_num = next_build_number(&config.project.version_num)?;
+    }
+    if let Some(bump) = bump {
+        let mut version = Version::parse(&config.project.version_string)?;
+        version.bump(bump, pre_release);
+        // Catches pre-release labels that aren't valid
+        config.project.version_string = Version::parse(&version.to_string())?.to_string();
+    }
+    config.save(project_path)?;
+    Ok(ProjectVersion {
+        version_num: config.project.version_num,
+        version_string: config.project.version_string,
+    })
+}
+
+// For a build number worked out before the build, which is only kept once it succeeded
+pub fn save_build_number(project_path: PathBuf, version_num: &str) -> Result<(), String> {
+    let mut config = TomlConfig::load_or_default(project_path.clone())?;
+    config.project.version_num = version_num.to_string();
+    config.save(project_path)
+}
+
+#[tauri::command]
+pub fn bump_version(
+    folder: String,
+    build: bool,
+    bump: Option<VersionBump>,
+    pre_release: Option<String>,
+) -> Result<ProjectVersion, String> {
+    bump_project_version(
+        PathBuf::from(folder),
+        build,
+        bump,
+        pre_release.as_deref().filter(|p| !p.is_empty()),
+    )
+}
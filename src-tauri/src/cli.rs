@@ -0,0 +1,529 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use dircpy::CopyBuilder;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tauri::{window::WindowBuilder, AppHandle, Emitter, Listener, Manager, Window};
+use tauri_plugin_cli::{ArgData, SubcommandMatches};
+use tauri_plugin_store::StoreExt;
+
+use crate::{
+    builder::{
+        config::{BuildRequest, BuildSettings, ProjectConfig, TargetPlatform, TomlConfig},
+        jobs::BuildJobs,
+        migrate::{migrate_project_config, MIGRATIONS},
+        packer::{pack, zip_ipa},
+        sdk::install_sdk_operation,
+        swift::{build_swift_internal, clean_swift, deploy_swift, validate_toolchain, SwiftBin},
+        symbols::{copy_dsym, dsyms_in},
+        version::{bump_project_version, VersionBump},
+    },
+    sideloader::{
+        device::{list_devices, DeviceInfo},
+        syslog::{start_stream_syslog, SyslogStream},
+    },
+};
+
+const DEFAULT_ANISETTE_SERVER: &str = "ani.sidestore.io";
+
+const EXIT_SUCCESS: i32 = 0;
+const EXIT_FAILURE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+enum CliError {
+    Usage(String),
+    Failed(String),
+}
+
+impl From<String> for CliError {
+    fn from(e: String) -> Self {
+        CliError::Failed(e)
+    }
+}
+
+// Runs a subcommand without showing any window. The commands still report through events
+// on a window, so one without a webview is created and never shown, and the events are
+// printed here instead of reaching the frontend.
+pub fn run(app: &AppHandle, subcommand: SubcommandMatches) {
+    attach_console();
+    if let Some(help) = subcommand.matches.args.get("help") {
+        println!("{}", help.value.as_str().unwrap_or_default());
+        app.exit(EXIT_SUCCESS);
+        return;
+    }
+
+    let window = match WindowBuilder::new(app, "main").visible(false).build() {
+        Ok(window) => window,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            app.exit(EXIT_FAILURE);
+            return;
+        }
+    };
+    print_events(app);
+    answer_login_prompts(app, &window);
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let ctrl_c_handle = handle.clone();
+        let ctrl_c_window = window.clone();
+        tauri::async_runtime::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                // Builds run in their own process group and would outlive us otherwise
+                let jobs = ctrl_c_handle.state::<BuildJobs>();
+                if let Some(job) = jobs
+                    .list()
+                    .into_iter()
+                    .find(|job| job.window == ctrl_c_window.label())
+                {
+                    jobs.cancel(&ctrl_c_window, Some(job.id)).ok();
+                }
+                ctrl_c_handle.exit(130);
+            }
+        });
+
+        let args = subcommand.matches.args;
+        let code = match run_subcommand(&handle, &window, &subcommand.name, &args).await {
+            Ok(()) => EXIT_SUCCESS,
+            Err(CliError::Usage(e)) => {
+                eprintln!("error: {}", e);
+                EXIT_USAGE
+            }
+            Err(CliError::Failed(e)) => {
+                eprintln!("error: {}", e);
+                EXIT_FAILURE
+            }
+        };
+        handle.exit(code);
+    });
+}
+
+async fn run_subcommand(
+    handle: &AppHandle,
+    window: &Window,
+    name: &str,
+    args: &HashMap<String, ArgData>,
+) -> Result<(), CliError> {
+    match name {
+        "build" => {
+            let folder = project_arg(args)?;
+            let toolchain_path = toolchain_arg(handle, args)?;
+            let build_settings = build_settings(&folder, args)?;
+            let platform = build_settings.platform;
+            let jobs = handle.state::<BuildJobs>();
+            let job = jobs.start(window, &folder, "build")?;
+            let packed = build_swift_internal(
+                window,
+                &folder,
+                &toolchain_path,
+                build_settings,
+                true,
+                job.process(),
+            )
+            .await?;
+            let output = package_output(packed.path.clone(), &packed.config, platform, args)?;
+            packed.commit_build_number(window)?;
+            job.succeed();
+            println!("{}", output.display());
+            Ok(())
+        }
+        // Packages whatever the last build produced without running swift build again
+        "package" => {
+            let folder = project_arg(args)?;
+            let toolchain_path = toolchain_arg(handle, args)?;
+            let build_settings = build_settings(&folder, args)?;
+            let config = ProjectConfig::load(
+                PathBuf::from(&folder),
+                &toolchain_path,
+                build_settings.configuration.as_deref(),
+                build_settings.app.as_deref(),
+            )?;
+            let build_settings = BuildSettings {
+                debug: config.debug.unwrap_or(build_settings.debug),
+                ..build_settings
+            };
+            let swift_bin = SwiftBin::new(&toolchain_path)?;
+            let jobs = handle.state::<BuildJobs>();
+            let job = jobs.start(window, &folder, "package")?;
+            let app = pack(
+                PathBuf::from(&folder),
+                &config,
+                &build_settings,
+                &swift_bin,
+                job.process(),
+                |line| {
+                    window.emit("build-output", line).ok();
+                },
+            )?;
+            let output = package_output(app, &config, build_settings.platform, args)?;
+            job.succeed();
+            println!("{}", output.display());
+            Ok(())
+        }
+        "deploy" => {
+            let folder = project_arg(args)?;
+            let toolchain_path = toolchain_arg(handle, args)?;
+            let build_settings = build_settings(&folder, args)?;
+            let device = device_arg(args).await?;
+            deploy_swift(
+                window.clone(),
+                handle.state::<BuildJobs>(),
+                anisette_arg(handle, args),
+                device,
+                folder,
+                toolchain_path,
+                BuildRequest {
+                    debug: build_settings.debug,
+                    configuration: build_settings.configuration,
+                    app: build_settings.app,
+                    platform: Some(build_settings.platform),
+                    arch: Some(build_settings.arch),
+                },
+            )
+            .await?;
+            Ok(())
+        }
+        "clean" => {
+            let folder = project_arg(args)?;
+            let toolchain_path = toolchain_arg(handle, args)?;
+            clean_swift(
+                window.clone(),
+                handle.state::<BuildJobs>(),
+                folder,
+                toolchain_path,
+            )
+            .await?;
+            Ok(())
+        }
+        "install-sdk" => {
+            let xcode_path = string_arg(args, "xcode")
+                .ok_or(CliError::Usage("Missing path to Xcode".to_string()))?;
+            let toolchain_path = toolchain_arg(handle, args)?;
+            let is_dir = PathBuf::from(&xcode_path).is_dir();
+            install_sdk_operation(
+                handle.clone(),
+                window.clone(),
+                xcode_path,
+                toolchain_path,
+                is_dir,
+            )
+            .await?;
+            Ok(())
+        }
+        "devices" => {
+            for device in list_devices().await? {
+                println!("{}\t{}", device.uuid, device.name);
+            }
+            Ok(())
+        }
+        // Streams until interrupted
+        "logs" => {
+            let device = device_arg(args).await?;
+            start_stream_syslog(window.clone(), device, handle.state::<SyslogStream>()).await?;
+            std::future::pending::<()>().await;
+            Ok(())
+        }
+        // Prints the current version when nothing is bumped
+        "version" => {
+            let folder = project_arg(args)?;
+            let bump: Option<VersionBump> = string_arg(args, "bump")
+                .map(|value| {
+                    serde_json::from_value(Value::String(value.clone()))
+                        .map_err(|_| CliError::Usage(format!("Invalid --bump: {}", value)))
+                })
+                .transpose()?;
+            let build = flag_arg(args, "build-number");
+            let (version_num, version_string) = if build || bump.is_some() {
+                let version = bump_project_version(
+                    PathBuf::from(&folder),
+                    build,
+                    bump,
+                    string_arg(args, "pre-release").as_deref(),
+                )?;
+                (version.version_num, version.version_string)
+            } else {
+                let toml_config = TomlConfig::load_or_default(PathBuf::from(&folder))?;
+                (
+                    toml_config.project.version_num,
+                    toml_config.project.version_string,
+                )
+            };
+            println!("{} ({})", version_string, version_num);
+            Ok(())
+        }
+        "migrate" => {
+            let folder = project_arg(args)?;
+            let dry_run = flag_arg(args, "dry-run");
+            let migration = migrate_project_config(&PathBuf::from(&folder), MIGRATIONS, dry_run)?;
+            if migration.from_version == migration.to_version {
+                println!(
+                    "crosscode.toml is already at version {}",
+                    migration.to_version
+                );
+                return Ok(());
+            }
+            println!("{}", migration.diff);
+            match migration.backup_path {
+                Some(backup) => println!(
+                    "Migrated crosscode.toml from version {} to {}, the original was saved to {}",
+                    migration.from_version, migration.to_version, backup
+                ),
+                None => println!(
+                    "Dry run, crosscode.toml would be migrated from version {} to {}",
+                    migration.from_version, migration.to_version
+                ),
+            }
+            Ok(())
+        }
+        _ => Err(CliError::Usage(format!("Unknown command: {}", name))),
+    }
+}
+
+fn print_events(app: &AppHandle) {
+    app.listen_any("build-output", |event| {
+        let line =
+            serde_json::from_str::<String>(event.payload()).unwrap_or(event.payload().to_string());
+        // Exit codes are reported through the process exit code instead
+        if !line.starts_with("command.done.") {
+            println!("{}", line);
+        }
+    });
+    app.listen_any("build-diagnostics-summary", |event| {
+        if let Ok(summary) = serde_json::from_str::<Value>(event.payload()) {
+            let errors = summary["errors"].as_u64().unwrap_or(0);
+            let warnings = summary["warnings"].as_u64().unwrap_or(0);
+            if errors > 0 || warnings > 0 {
+                eprintln!("{} error(s), {} warning(s)", errors, warnings);
+            }
+        }
+    });
+    app.listen_any("operation_install_sdk", |event| {
+        if let Ok(update) = serde_json::from_str::<Value>(event.payload()) {
+            let step = update["stepId"].as_str().unwrap_or_default();
+            match update["updateType"].as_str() {
+                Some("started") => println!("==> {}", step),
+                Some("failed") => eprintln!(
+                    "==> {} failed: {}",
+                    step,
+                    update["extraDetails"].as_str().unwrap_or_default()
+                ),
+                _ => {}
+            }
+        }
+    });
+    app.listen_any("syslog-message", |event| {
+        let line =
+            serde_json::from_str::<String>(event.payload()).unwrap_or(event.payload().to_string());
+        println!("{}", line);
+    });
+}
+
+// There is no login dialog in headless mode, credentials come from the keychain (saved by
+// the GUI) or the environment, and 2FA codes are read from the terminal
+fn answer_login_prompts(app: &AppHandle, window: &Window) {
+    let prompt_window = window.clone();
+    // The login closure is already listening for the reply when it asks, replies are sent
+    // from another thread so they aren't emitted from inside this handler
+    app.listen_any("apple-id-required", move |_| {
+        let window = prompt_window.clone();
+        std::thread::spawn(move || reply_apple_id(&window));
+    });
+
+    let prompt_window = window.clone();
+    app.listen_any("2fa-required", move |_| {
+        let window = prompt_window.clone();
+        std::thread::spawn(move || {
+            eprint!("Two-factor authentication code: ");
+            io::stderr().flush().ok();
+            let mut code = String::new();
+            io::stdin().lock().read_line(&mut code).ok();
+            window.emit("2fa-recieved", code.trim().to_string()).ok();
+        });
+    });
+}
+
+fn reply_apple_id(window: &Window) {
+    match (
+        std::env::var("CROSSCODE_APPLE_ID"),
+        std::env::var("CROSSCODE_APPLE_PASSWORD"),
+    ) {
+        (Ok(apple_id), Ok(password)) => {
+            window
+                .emit(
+                    "apple-id-recieved",
+                    serde_json::json!({
+                        "appleId": apple_id,
+                        "applePass": password,
+                        "saveCredentials": false,
+                    }),
+                )
+                .ok();
+        }
+        _ => {
+            eprintln!(
+                "No saved Apple ID, sign in once from the app or set CROSSCODE_APPLE_ID and CROSSCODE_APPLE_PASSWORD"
+            );
+            window.emit("login-cancelled", ()).ok();
+        }
+    }
+}
+
+fn string_arg(args: &HashMap<String, ArgData>, name: &str) -> Option<String> {
+    args.get(name)
+        .and_then(|arg| arg.value.as_str())
+        .map(|s| s.to_string())
+}
+
+fn flag_arg(args: &HashMap<String, ArgData>, name: &str) -> bool {
+    args.get(name)
+        .is_some_and(|arg| arg.value.as_bool() == Some(true))
+}
+
+fn project_arg(args: &HashMap<String, ArgData>) -> Result<String, CliError> {
+    let project = match string_arg(args, "project") {
+        Some(project) => PathBuf::from(project),
+        None => std::env::current_dir().map_err(|e| e.to_string())?,
+    };
+    if !project.join("crosscode.toml").exists() {
+        return Err(CliError::Usage(format!(
+            "{} is not a CrossCode project",
+            project.display()
+        )));
+    }
+    Ok(project.to_string_lossy().to_string())
+}
+
+fn toolchain_arg(handle: &AppHandle, args: &HashMap<String, ArgData>) -> Result<String, CliError> {
+    let toolchain_path = string_arg(args, "toolchain").or_else(|| {
+        let store = handle.store("preferences.json").ok()?;
+        let toolchain = store.get("swift/selected-toolchain")?;
+        toolchain["path"].as_str().map(|s| s.to_string())
+    });
+    match toolchain_path {
+        Some(path) if validate_toolchain(&path) => Ok(path),
+        Some(path) => Err(CliError::Usage(format!("Invalid toolchain: {}", path))),
+        None => Err(CliError::Usage(
+            "No toolchain selected, pass --toolchain or pick one in the app".to_string(),
+        )),
+    }
+}
+
+fn anisette_arg(handle: &AppHandle, args: &HashMap<String, ArgData>) -> String {
+    string_arg(args, "anisette")
+        .or_else(|| {
+            let store = handle.store("preferences.json").ok()?;
+            store
+                .get("apple-id/anisette-server")?
+                .as_str()
+                .map(|s| s.to_string())
+        })
+        .unwrap_or(DEFAULT_ANISETTE_SERVER.to_string())
+}
+
+// --config takes a configuration from crosscode.toml, or plain debug/release
+fn build_settings(
+    folder: &str,
+    args: &HashMap<String, ArgData>,
+) -> Result<BuildSettings, CliError> {
+    let (debug, configuration) = match string_arg(args, "config") {
+        None => (true, None),
+        Some(name) => {
+            let toml_config = TomlConfig::load_or_default(PathBuf::from(folder))?;
+            if toml_config.configurations.contains_key(&name) {
+                (true, Some(name))
+            } else if name == "debug" || name == "release" {
+                (name == "debug", None)
+            } else {
+                return Err(CliError::Usage(
+                    toml_config
+                        .configuration(Some(&name))
+                        .err()
+                        .unwrap_or_default(),
+                ));
+            }
+        }
+    };
+    Ok(BuildSettings {
+        debug,
+        configuration,
+        app: string_arg(args, "app"),
+        platform: enum_arg(args, "platform")?,
+        arch: enum_arg(args, "arch")?,
+    })
+}
+
+// Parses a value with the same names the frontend uses, e.g. ios-simulator or x86_64
+fn enum_arg<T: DeserializeOwned + Default>(
+    args: &HashMap<String, ArgData>,
+    name: &str,
+) -> Result<T, CliError> {
+    match string_arg(args, name) {
+        Some(value) => serde_json::from_value(Value::String(value.clone()))
+            .map_err(|_| CliError::Usage(format!("Invalid --{}: {}", name, value))),
+        None => Ok(T::default()),
+    }
+}
+
+async fn device_arg(args: &HashMap<String, ArgData>) -> Result<DeviceInfo, CliError> {
+    let devices = list_devices().await?;
+    match string_arg(args, "device") {
+        Some(udid) => devices
+            .into_iter()
+            .find(|d| d.uuid == udid || d.name == udid)
+            .ok_or(CliError::Usage(format!("Device {} is not connected", udid))),
+        None if devices.len() == 1 => Ok(devices.into_iter().next().unwrap()),
+        None if devices.is_empty() => Err(CliError::Failed("No devices connected".to_string())),
+        None => Err(CliError::Usage(
+            "Multiple devices connected, pick one with --device".to_string(),
+        )),
+    }
+}
+
+// Device builds become an IPA, simulator and macOS builds are used as the .app itself
+fn package_output(
+    app: PathBuf,
+    config: &ProjectConfig,
+    platform: TargetPlatform,
+    args: &HashMap<String, ArgData>,
+) -> Result<PathBuf, CliError> {
+    let output = if platform == TargetPlatform::Ios {
+        zip_ipa(app, config)?
+    } else {
+        app
+    };
+    let out = match string_arg(args, "out") {
+        Some(out) => PathBuf::from(out),
+        None => return Ok(output),
+    };
+    if output.is_dir() {
+        CopyBuilder::new(&output, &out)
+            .overwrite(true)
+            .run()
+            .map_err(|e| format!("Failed to copy app to {}: {}", out.display(), e))?;
+    } else {
+        std::fs::copy(&output, &out)
+            .map_err(|e| format!("Failed to copy IPA to {}: {}", out.display(), e))?;
+        // The dSYMs saved beside the IPA go along with it
+        let out_dir = match out.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        for dsym in output.parent().map(dsyms_in).unwrap_or_default() {
+            copy_dsym(&dsym, out_dir)?;
+        }
+    }
+    Ok(out)
+}
+
+// Release builds on Windows are GUI subsystem apps with no console of their own
+fn attach_console() {
+    #[cfg(target_os = "windows")]
+    unsafe {
+        use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
@@ -2,7 +2,7 @@ use std::path::PathBuf;
 
 use sysinfo::System;
 
-use crate::builder::config::{ProjectConfig, ProjectValidation};
+use crate::builder::project_validation::{validate_project_dir, ValidationFinding};
 
 #[tauri::command]
 pub fn has_limited_ram() -> bool {
@@ -13,8 +13,8 @@ pub fn has_limited_ram() -> bool {
 }
 
 #[tauri::command]
-pub fn validate_project(project_path: String, toolchain_path: String) -> ProjectValidation {
-    ProjectConfig::validate(PathBuf::from(project_path), &toolchain_path)
+pub fn validate_project(project_path: String, toolchain_path: String) -> Vec<ValidationFinding> {
+    validate_project_dir(&PathBuf::from(project_path), &toolchain_path)
 }
 
 // #[tauri::command]
@@ -7,6 +7,7 @@ mod templates;
 mod windows;
 #[macro_use]
 mod builder;
+mod cli;
 mod operation;
 #[macro_use]
 mod sideloader;
@@ -15,13 +16,25 @@ mod sourcekit_lsp;
 #[macro_use]
 mod lsp_utils;
 
+use builder::config::{list_apps, list_configurations};
 use builder::crossplatform::{linux_path, windows_path};
+use builder::dependencies::{
+    add_dependency, check_outdated_dependencies, list_dependencies, remove_dependency,
+    resolve_packages, update_packages,
+};
 use builder::icon::import_icon;
+use builder::jobs::{cancel_build, list_build_jobs, BuildJobs};
+use builder::macho::inspect_macho;
+use builder::migrate::{migrate_config, preview_config_migration};
+use builder::package::dump_package;
+use builder::project_validation::apply_validation_fix;
 use builder::sdk::install_sdk_operation;
 use builder::swift::{
     build_swift, clean_swift, deploy_swift, get_swiftly_toolchains, get_toolchain_info,
     has_darwin_sdk, validate_toolchain,
 };
+use builder::validator::validate_app_bundle;
+use builder::version::bump_version;
 use lsp_utils::{has_limited_ram, validate_project};
 use serde_json::Value;
 use sideloader::{
@@ -29,9 +42,10 @@ use sideloader::{
         delete_app_id, delete_stored_credentials, get_apple_email, get_certificates, list_app_ids,
         reset_anisette, revoke_certificate,
     },
+    crash_reports::{list_crash_reports, open_crash_report, pull_crash_report},
     device::{is_ddi_mounted, mount_ddi},
     screenshot::take_screenshot,
-    sideload::refresh_idevice,
+    sideload::{install_ipa, refresh_idevice},
     stdout::{is_streaming_stdout, start_stream_stdout, stop_stream_stdout, StdoutStream},
     syslog::{is_streaming_syslog, start_stream_syslog, stop_stream_syslog, SyslogStream},
 };
@@ -39,6 +53,7 @@ use sourcekit_lsp::{get_server_status, start_sourcekit_server, stop_sourcekit_se
 use std::sync::Arc;
 use tauri::Emitter;
 use tauri::Manager;
+use tauri::WebviewWindowBuilder;
 use tauri_plugin_cli::CliExt;
 use tauri_plugin_store::StoreExt;
 use templates::create_template;
@@ -72,18 +87,31 @@ fn main() {
         .manage(sourcekit_lsp::create_server_state())
         .manage(syslog_stream)
         .manage(stdout_stream)
+        .manage(BuildJobs::default())
         .setup(|app| {
-            match app.cli().matches() {
-                Ok(matches) => {
-                    if let Some(arg) = matches.args.get("showMainWindow") {
-                        if arg.value == Value::Bool(true) {
-                            let window = app.get_webview_window("main").unwrap();
-                            window.show().unwrap();
-                            window.open_devtools();
-                        }
-                    }
+            let mut show_main_window = false;
+            if let Ok(matches) = app.cli().matches() {
+                // Subcommands run headless and never show the IDE
+                if let Some(subcommand) = matches.subcommand {
+                    cli::run(app.handle(), *subcommand);
+                    return Ok(());
                 }
-                Err(_) => {}
+                show_main_window = matches
+                    .args
+                    .get("showMainWindow")
+                    .is_some_and(|arg| arg.value == Value::Bool(true));
+            }
+
+            // The windows in tauri.conf.json aren't created on startup so that subcommands
+            // don't open any
+            for window_config in &app.config().app.windows {
+                WebviewWindowBuilder::from_config(app.handle(), window_config)?.build()?;
+            }
+
+            if show_main_window {
+                let window = app.get_webview_window("main").unwrap();
+                window.show().unwrap();
+                window.open_devtools();
             }
 
             let store = app.store("preferences.json")?;
@@ -122,6 +150,8 @@ fn main() {
             build_swift,
             deploy_swift,
             clean_swift,
+            cancel_build,
+            list_build_jobs,
             refresh_idevice,
             delete_stored_credentials,
             reset_anisette,
@@ -155,6 +185,25 @@ fn main() {
             is_ddi_mounted,
             mount_ddi,
             take_screenshot,
+            list_crash_reports,
+            pull_crash_report,
+            open_crash_report,
+            install_ipa,
+            validate_app_bundle,
+            inspect_macho,
+            bump_version,
+            list_configurations,
+            list_apps,
+            preview_config_migration,
+            migrate_config,
+            apply_validation_fix,
+            dump_package,
+            list_dependencies,
+            add_dependency,
+            remove_dependency,
+            resolve_packages,
+            update_packages,
+            check_outdated_dependencies,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -88,10 +88,7 @@ pub async fn login(
             return Ok((email, password));
         }
 
-        window_clone
-            .emit("apple-id-required", ())
-            .expect("Failed to emit apple-id-required event");
-
+        // Listening before asking, so a reply that comes right away isn't missed
         let tx1 = tx.clone();
         let handler_id_recieved = window_clone.listen("apple-id-recieved", move |event| {
             let json = event.payload();
@@ -103,6 +100,10 @@ pub async fn login(
             let _ = tx2.send("login-cancelled".to_string());
         });
 
+        window_clone
+            .emit("apple-id-required", ())
+            .expect("Failed to emit apple-id-required event");
+
         let result = rx.recv_timeout(Duration::from_secs(120));
         window_clone.unlisten(handler_id_recieved);
         window_clone.unlisten(handler_id_cancelled);
@@ -157,16 +158,16 @@ pub async fn login(
     let (tx, rx) = std::sync::mpsc::channel::<String>();
     let window_clone = window.clone();
     let tfa_closure = move || -> Result<String, String> {
-        window_clone
-            .emit("2fa-required", ())
-            .expect("Failed to emit 2fa-required event");
-
         let tx = tx.clone();
         let handler_id = window_clone.listen("2fa-recieved", move |event| {
             let code = event.payload();
             let _ = tx.send(code.to_string());
         });
 
+        window_clone
+            .emit("2fa-required", ())
+            .expect("Failed to emit 2fa-required event");
+
         let result = rx.recv_timeout(Duration::from_secs(120));
         window_clone.unlisten(handler_id);
 
@@ -0,0 +1,241 @@
+use isideload::developer_session::{
+    AppId, ApplicationGroup, DeveloperDeviceType, DeveloperSession, DeveloperTeam,
+};
+use plist::{Dictionary, Value};
+use tauri::{Emitter, Window};
+
+use crate::builder::entitlements::capability;
+
+const LIST_TEAMS_URL: &str =
+    "https://developerservices2.apple.com/services/QH65B2/listTeams.action";
+
+const APP_GROUPS_ENTITLEMENT: &str = "com.apple.security.application-groups";
+
+// The team's account type as the developer portal reports it. Free teams show up as
+// "Individual" with only the free provisioning membership.
+pub async fn is_free_account(
+    dev_session: &DeveloperSession,
+    team: &DeveloperTeam,
+) -> Result<bool, String> {
+    let response = dev_session
+        .send_developer_request(LIST_TEAMS_URL, None)
+        .await
+        .map_err(|e| format!("Failed to list teams: {:?}", e))?;
+    let team_info = response
+        .get("teams")
+        .and_then(Value::as_array)
+        .and_then(|teams| {
+            teams
+                .iter()
+                .filter_map(Value::as_dictionary)
+                .find(|t| t.get("teamId").and_then(Value::as_string) == Some(team.team_id.as_str()))
+        })
+        .ok_or(format!("Team {} was not found", team.team_id))?;
+    if team_info.get("type").and_then(Value::as_string) != Some("Individual") {
+        return Ok(false);
+    }
+    let memberships: Vec<String> = team_info
+        .get("memberships")
+        .and_then(Value::as_array)
+        .map(|m| {
+            m.iter()
+                .filter_map(|m| m.as_dictionary()?.get("name")?.as_string())
+                .map(|name| name.to_lowercase())
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(!memberships.is_empty() && memberships.iter().all(|name| name.contains("free")))
+}
+
+// Makes sure the App ID has every feature and app group the requested entitlements need
+// before the provisioning profile is generated, and returns the entitlements to sign the
+// bundle with: only what was granted, with identifiers rewritten for the team
+pub async fn prepare_app_id(
+    dev_session: &DeveloperSession,
+    window: &Window,
+    team: &DeveloperTeam,
+    free_account: bool,
+    identifier: &str,
+    name: &str,
+    entitlements: &Dictionary,
+) -> Result<Dictionary, String> {
+    let mut granted = Dictionary::new();
+    let mut features = Vec::new();
+    for (key, value) in entitlements {
+        match capability(key) {
+            Some(c) if c.free_account || !free_account => {
+                if let Some(feature) = c.feature {
+                    if !features.contains(&feature) {
+                        features.push(feature);
+                    }
+                }
+                let mut value = team_value(value, &team.team_id);
+                // Keychain groups outside the team's prefix can't be signed for
+                if key == "keychain-access-groups" {
+                    if let Value::Array(groups) = &mut value {
+                        for group in groups.iter_mut() {
+                            if let Value::String(g) = group {
+                                if !g.starts_with(&format!("{}.", team.team_id)) {
+                                    *g = format!("{}.{}", team.team_id, g);
+                                }
+                            }
+                        }
+                    }
+                }
+                granted.insert(key.clone(), value);
+            }
+            Some(_) => warn(
+                window,
+                &format!(
+                    "{} requires a paid developer account and will not be granted",
+                    key
+                ),
+            ),
+            None => warn(
+                window,
+                &format!(
+                    "{} is not a capability CrossCode can enable and will not be granted",
+                    key
+                ),
+            ),
+        }
+    }
+    if granted.is_empty() {
+        return Ok(granted);
+    }
+
+    let app_id = find_or_add_app_id(dev_session, team, name, identifier).await?;
+
+    let mut app_features = app_id.features.clone();
+    let mut changed = false;
+    for feature in features {
+        if app_features.get(feature).and_then(Value::as_boolean) != Some(true) {
+            app_features.insert(feature.to_string(), Value::Boolean(true));
+            changed = true;
+        }
+    }
+    if changed {
+        window
+            .emit(
+                "build-output",
+                format!("Enabling capabilities on App ID {}", identifier),
+            )
+            .ok();
+        dev_session
+            .update_app_id(DeveloperDeviceType::Ios, team, &app_id, &app_features)
+            .await
+            .map_err(|e| format!("Failed to update App ID {}: {:?}", identifier, e))?;
+    }
+
+    if let Some(Value::Array(groups)) = granted.get(APP_GROUPS_ENTITLEMENT) {
+        let groups: Vec<String> = groups
+            .iter()
+            .filter_map(Value::as_string)
+            .map(|g| g.to_string())
+            .collect();
+        let registered = assign_app_groups(dev_session, window, team, &app_id, &groups).await?;
+        granted.insert(
+            APP_GROUPS_ENTITLEMENT.to_string(),
+            Value::Array(registered.into_iter().map(Value::String).collect()),
+        );
+    }
+
+    Ok(granted)
+}
+
+async fn find_or_add_app_id(
+    dev_session: &DeveloperSession,
+    team: &DeveloperTeam,
+    name: &str,
+    identifier: &str,
+) -> Result<AppId, String> {
+    let find = || async {
+        dev_session
+            .list_app_ids(DeveloperDeviceType::Ios, team)
+            .await
+            .map_err(|e| format!("Failed to list App IDs: {:?}", e))
+            .map(|response| {
+                response
+                    .app_ids
+                    .into_iter()
+                    .find(|app_id| app_id.identifier == identifier)
+            })
+    };
+    if let Some(app_id) = find().await? {
+        return Ok(app_id);
+    }
+    dev_session
+        .add_app_id(DeveloperDeviceType::Ios, team, name, identifier)
+        .await
+        .map_err(|e| format!("Failed to register App ID {}: {:?}", identifier, e))?;
+    find()
+        .await?
+        .ok_or(format!("App ID {} was not registered", identifier))
+}
+
+// App group identifiers are unique across every team, so like bundle IDs they get the team
+// ID appended. Returns the identifiers the groups were registered as.
+async fn assign_app_groups(
+    dev_session: &DeveloperSession,
+    window: &Window,
+    team: &DeveloperTeam,
+    app_id: &AppId,
+    groups: &[String],
+) -> Result<Vec<String>, String> {
+    let mut existing = dev_session
+        .list_application_groups(DeveloperDeviceType::Ios, team)
+        .await
+        .map_err(|e| format!("Failed to list app groups: {:?}", e))?;
+    let mut registered = Vec::new();
+    for group in groups {
+        let identifier = if group.ends_with(&format!(".{}", team.team_id)) {
+            group.clone()
+        } else {
+            format!("{}.{}", group, team.team_id)
+        };
+        let app_group = match existing.iter().find(|g| g.identifier == identifier) {
+            Some(app_group) => app_group.clone(),
+            None => {
+                let app_group: ApplicationGroup = dev_session
+                    .add_application_group(DeveloperDeviceType::Ios, team, &identifier, group)
+                    .await
+                    .map_err(|e| format!("Failed to register app group {}: {:?}", identifier, e))?;
+                existing.push(app_group.clone());
+                app_group
+            }
+        };
+        dev_session
+            .assign_application_group_to_app_id(DeveloperDeviceType::Ios, team, app_id, &app_group)
+            .await
+            .map_err(|e| format!("Failed to assign app group {}: {:?}", identifier, e))?;
+        if identifier != *group {
+            window
+                .emit(
+                    "build-output",
+                    format!("App group {} is registered as {}", group, identifier),
+                )
+                .ok();
+        }
+        registered.push(identifier);
+    }
+    Ok(registered)
+}
+
+// Fills in the team ID where Xcode would, e.g. in keychain access groups
+fn team_value(value: &Value, team_id: &str) -> Value {
+    let prefix = format!("{}.", team_id);
+    match value {
+        Value::String(s) => Value::String(
+            s.replace("$(AppIdentifierPrefix)", &prefix)
+                .replace("$(TeamIdentifierPrefix)", &prefix),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(|v| team_value(v, team_id)).collect()),
+        other => other.clone(),
+    }
+}
+
+fn warn(window: &Window, message: &str) {
+    window
+        .emit("build-output", format!("Warning: {}", message))
+        .ok();
+}
@@ -0,0 +1,389 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use idevice::{
+    crashreportcopymobile::{flush_reports, CrashReportCopyMobileClient},
+    IdeviceService,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    builder::{
+        config::ProjectConfig,
+        macho::{read_macho, MachO},
+        swift::SwiftBin,
+        symbols::{demangle, find_local_binary},
+    },
+    sideloader::device::{get_provider, DeviceInfo},
+};
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReportEntry {
+    pub name: String,
+    pub process: String,
+    pub timestamp: String,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    pub name: String,
+    pub process: String,
+    pub bundle_id: Option<String>,
+    pub app_version: Option<String>,
+    pub os_version: Option<String>,
+    pub timestamp: Option<String>,
+    pub exception: Option<CrashException>,
+    pub termination_reason: Option<String>,
+    pub threads: Vec<CrashThread>,
+    pub images: Vec<CrashImage>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashException {
+    pub exception_type: Option<String>,
+    pub signal: Option<String>,
+    pub codes: Option<String>,
+    pub subtype: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashThread {
+    pub index: usize,
+    pub name: Option<String>,
+    pub queue: Option<String>,
+    pub crashed: bool,
+    pub frames: Vec<CrashFrame>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashFrame {
+    pub image: String,
+    pub image_index: usize,
+    pub address: u64,
+    pub image_offset: u64,
+    pub symbol: Option<String>,
+    pub symbol_offset: Option<u64>,
+    // Resolved against a local build rather than by the device
+    pub symbolicated: bool,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashImage {
+    pub name: String,
+    pub uuid: Option<String>,
+    pub base: u64,
+    pub local_binary: Option<String>,
+}
+
+// The .ips format is a one line JSON header followed by the report as another JSON document
+#[derive(Deserialize)]
+struct IpsHeader {
+    bug_type: Option<String>,
+    timestamp: Option<String>,
+    os_version: Option<String>,
+    #[serde(rename = "bundleID")]
+    bundle_id: Option<String>,
+    app_version: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IpsBody {
+    proc_name: Option<String>,
+    exception: Option<IpsException>,
+    termination: Option<IpsTermination>,
+    #[serde(default)]
+    threads: Vec<IpsThread>,
+    #[serde(default)]
+    used_images: Vec<IpsImage>,
+}
+
+#[derive(Deserialize)]
+struct IpsException {
+    #[serde(rename = "type")]
+    exception_type: Option<String>,
+    signal: Option<String>,
+    codes: Option<String>,
+    subtype: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct IpsTermination {
+    indicator: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct IpsThread {
+    #[serde(default)]
+    triggered: bool,
+    name: Option<String>,
+    queue: Option<String>,
+    #[serde(default)]
+    frames: Vec<IpsFrame>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IpsFrame {
+    image_offset: u64,
+    image_index: usize,
+    symbol: Option<String>,
+    symbol_location: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct IpsImage {
+    base: u64,
+    uuid: Option<String>,
+    name: Option<String>,
+}
+
+#[tauri::command]
+pub async fn list_crash_reports(
+    device: DeviceInfo,
+    folder: String,
+    toolchain_path: String,
+) -> Result<Vec<CrashReportEntry>, String> {
+    let processes = project_processes(&folder, &toolchain_path)?;
+    let provider = get_provider(&device).await?;
+    // Moves reports the device hasn't processed yet to where the copy service can see them
+    flush_reports(&provider)
+        .await
+        .map_err(|e| format!("Failed to flush crash reports: {}", e))?;
+    let mut client = CrashReportCopyMobileClient::connect(&provider)
+        .await
+        .map_err(|e| format!("Failed to connect to crash report service: {}", e))?;
+    let names = client
+        .ls(None)
+        .await
+        .map_err(|e| format!("Failed to list crash reports: {}", e))?;
+
+    let file_name = Regex::new(r"^(.+)-(\d{4}-\d{2}-\d{2})-(\d{2})(\d{2})(\d{2})\.ips$").unwrap();
+    let mut reports: Vec<CrashReportEntry> = names
+        .into_iter()
+        .filter_map(|name| {
+            let captures = file_name.captures(&name)?;
+            let process = captures[1].to_string();
+            if !processes.contains(&process) {
+                return None;
+            }
+            let timestamp = format!(
+                "{} {}:{}:{}",
+                &captures[2], &captures[3], &captures[4], &captures[5]
+            );
+            Some(CrashReportEntry {
+                name: name.clone(),
+                process,
+                timestamp,
+            })
+        })
+        .collect();
+    reports.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    Ok(reports)
+}
+
+// Pulls a report, keeps a copy in .crosscode/CrashReports and symbolicates it
+#[tauri::command]
+pub async fn pull_crash_report(
+    device: DeviceInfo,
+    folder: String,
+    toolchain_path: String,
+    name: String,
+) -> Result<CrashReport, String> {
+    if name.contains('/') || name.contains('\\') {
+        return Err(format!("Invalid crash report name: {}", name));
+    }
+    let provider = get_provider(&device).await?;
+    let mut client = CrashReportCopyMobileClient::connect(&provider)
+        .await
+        .map_err(|e| format!("Failed to connect to crash report service: {}", e))?;
+    let data = client
+        .pull(name.clone())
+        .await
+        .map_err(|e| format!("Failed to pull {}: {}", name, e))?;
+
+    let reports_dir = PathBuf::from(&folder)
+        .join(".crosscode")
+        .join("CrashReports");
+    fs::create_dir_all(&reports_dir)
+        .map_err(|e| format!("Failed to create {}: {}", reports_dir.display(), e))?;
+    fs::write(reports_dir.join(&name), &data)
+        .map_err(|e| format!("Failed to save {}: {}", name, e))?;
+
+    let content = String::from_utf8_lossy(&data);
+    symbolicate_report(&folder, &toolchain_path, &name, &content)
+}
+
+// For reports shared by testers from Settings > Privacy > Analytics
+#[tauri::command]
+pub async fn open_crash_report(
+    folder: String,
+    toolchain_path: String,
+    path: String,
+) -> Result<CrashReport, String> {
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let name = Path::new(&path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or(path.clone());
+    symbolicate_report(&folder, &toolchain_path, &name, &content)
+}
+
+// The apps and extensions, which is what crash reports are named after
+fn project_processes(folder: &str, toolchain_path: &str) -> Result<Vec<String>, String> {
+    let config = ProjectConfig::load(PathBuf::from(folder), toolchain_path, None, None)?;
+    let mut processes = vec![config.product.clone()];
+    processes.extend(config.extensions.into_iter().map(|e| e.target));
+    processes.extend(config.apps);
+    Ok(processes)
+}
+
+fn symbolicate_report(
+    folder: &str,
+    toolchain_path: &str,
+    name: &str,
+    content: &str,
+) -> Result<CrashReport, String> {
+    let mut report = parse_ips(name, content)?;
+    let project_path = Path::new(folder);
+
+    let mut binaries: HashMap<usize, MachO> = HashMap::new();
+    for (index, image) in report.images.iter_mut().enumerate() {
+        let uuid = match &image.uuid {
+            Some(uuid) => uuid,
+            None => continue,
+        };
+        let path = match find_local_binary(project_path, uuid) {
+            Some(path) => path,
+            None => continue,
+        };
+        let slice = read_macho(&path)?
+            .into_iter()
+            .find(|slice| slice.uuid.as_deref() == Some(uuid.as_str()));
+        if let Some(slice) = slice {
+            image.local_binary = Some(path.to_string_lossy().to_string());
+            binaries.insert(index, slice);
+        }
+    }
+
+    let mut mangled = Vec::new();
+    for thread in report.threads.iter_mut() {
+        for frame in thread.frames.iter_mut() {
+            let binary = match binaries.get(&frame.image_index) {
+                Some(binary) => binary,
+                None => continue,
+            };
+            if let Some((symbol, offset)) = binary.lookup(binary.text_vmaddr + frame.image_offset) {
+                // C symbol names get an underscore prepended on Darwin
+                let name = symbol.name.strip_prefix('_').unwrap_or(&symbol.name);
+                frame.symbol = Some(name.to_string());
+                frame.symbol_offset = Some(offset);
+                frame.symbolicated = true;
+                mangled.push(name.to_string());
+            }
+        }
+    }
+
+    // Symbols are still useful mangled if the toolchain can't demangle them
+    let demangled = SwiftBin::new(toolchain_path)
+        .and_then(|swift_bin| demangle(&swift_bin, &mangled))
+        .unwrap_or_default();
+    let names: HashMap<String, String> = mangled.into_iter().zip(demangled).collect();
+    for frame in report
+        .threads
+        .iter_mut()
+        .flat_map(|t| t.frames.iter_mut())
+        .filter(|f| f.symbolicated)
+    {
+        if let Some(name) = frame.symbol.as_ref().and_then(|s| names.get(s)) {
+            frame.symbol = Some(name.clone());
+        }
+    }
+
+    Ok(report)
+}
+
+fn parse_ips(name: &str, content: &str) -> Result<CrashReport, String> {
+    let (header, body) = content.split_once('\n').ok_or(format!(
+        "{} is not an .ips crash report, only the JSON format of iOS 15 and later is supported",
+        name
+    ))?;
+    let header: IpsHeader = serde_json::from_str(header)
+        .map_err(|e| format!("Failed to parse {} header: {}", name, e))?;
+    if let Some(bug_type) = header.bug_type.as_deref().filter(|t| *t != "309") {
+        return Err(format!(
+            "{} is not a crash report (bug type {})",
+            name, bug_type
+        ));
+    }
+    let body: IpsBody =
+        serde_json::from_str(body).map_err(|e| format!("Failed to parse {}: {}", name, e))?;
+
+    let images: Vec<CrashImage> = body
+        .used_images
+        .into_iter()
+        .map(|image| CrashImage {
+            name: image.name.unwrap_or("???".to_string()),
+            uuid: image.uuid.map(|u| u.to_uppercase()),
+            base: image.base,
+            local_binary: None,
+        })
+        .collect();
+
+    let threads = body
+        .threads
+        .into_iter()
+        .enumerate()
+        .map(|(index, thread)| CrashThread {
+            index,
+            name: thread.name,
+            queue: thread.queue,
+            crashed: thread.triggered,
+            frames: thread
+                .frames
+                .into_iter()
+                .map(|frame| {
+                    let image = images.get(frame.image_index);
+                    CrashFrame {
+                        image: image.map_or("???".to_string(), |i| i.name.clone()),
+                        image_index: frame.image_index,
+                        address: image.map_or(0, |i| i.base) + frame.image_offset,
+                        image_offset: frame.image_offset,
+                        symbol: frame.symbol,
+                        symbol_offset: frame.symbol_location,
+                        symbolicated: false,
+                    }
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(CrashReport {
+        name: name.to_string(),
+        process: body.proc_name.unwrap_or_default(),
+        bundle_id: header.bundle_id,
+        app_version: header.app_version,
+        os_version: header.os_version,
+        timestamp: header.timestamp,
+        exception: body.exception.map(|e| CrashException {
+            exception_type: e.exception_type,
+            signal: e.signal,
+            codes: e.codes,
+            subtype: e.subtype,
+        }),
+        termination_reason: body.termination.and_then(|t| t.indicator),
+        threads,
+        images,
+    })
+}
@@ -1,5 +1,7 @@
 pub mod apple;
 pub mod apple_commands;
+pub mod capabilities;
+pub mod crash_reports;
 pub mod device;
 pub mod screenshot;
 pub mod sideload;
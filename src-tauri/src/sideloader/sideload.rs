@@ -1,7 +1,21 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
-use crate::sideloader::device::{get_provider, list_devices, DeviceInfo};
+use crate::{
+    builder::macho::read_macho,
+    sideloader::{
+        capabilities::{is_free_account, prepare_app_id},
+        device::{get_provider, list_devices, DeviceInfo},
+    },
+};
 use isideload::{sideload, Error, SideloadConfiguration, SideloadLogger};
+use plist::{Dictionary, Value};
 use tauri::{Emitter, Manager, Window};
 
 pub struct TauriLogger {
@@ -26,19 +40,73 @@ pub async fn sideload_app(
     anisette_server: String,
     device: DeviceInfo,
     app_path: PathBuf,
+    entitlements: &[(PathBuf, Dictionary)],
 ) -> Result<(), String> {
     let dev_session =
         crate::sideloader::apple::get_developer_session(&handle, &window, anisette_server.clone())
             .await?;
+    let mut signing_entitlements = Dictionary::new();
+    if !entitlements.is_empty() {
+        let team = dev_session
+            .get_team()
+            .await
+            .map_err(|e| format!("Failed to get developer team: {:?}", e))?;
+        let free_account = is_free_account(&dev_session, &team).await?;
+        let (main_bundle_id, _) = read_bundle_info(&app_path)?;
+        let mut extensions_granted = Vec::new();
+        for (bundle_path, bundle_entitlements) in entitlements {
+            let (bundle_id, name) = read_bundle_info(bundle_path)?;
+            // isideload appends the team ID to the main app's bundle ID and nests extensions
+            // under it
+            let identifier = match bundle_id.strip_prefix(&format!("{}.", main_bundle_id)) {
+                Some(suffix) => format!("{}.{}.{}", main_bundle_id, team.team_id, suffix),
+                None => format!("{}.{}", bundle_id, team.team_id),
+            };
+            let granted = prepare_app_id(
+                &dev_session,
+                window,
+                &team,
+                free_account,
+                &identifier,
+                &name,
+                bundle_entitlements,
+            )
+            .await?;
+            if *bundle_path == app_path {
+                signing_entitlements = granted;
+            } else {
+                extensions_granted.push((name, granted));
+            }
+        }
+        // Extensions are signed with the app's provisioning profile and entitlements
+        for (name, granted) in extensions_granted {
+            for key in granted.keys() {
+                if !signing_entitlements.contains_key(key) {
+                    window
+                        .emit(
+                            "build-output",
+                            format!(
+                                "Warning: {} of {} will not be granted, extensions are signed with the app's entitlements",
+                                key, name
+                            ),
+                        )
+                        .ok();
+                }
+            }
+        }
+    }
     let logger = TauriLogger {
         window: Arc::new(window.clone()),
     };
     let store_dir = handle.path().app_config_dir().map_err(|e| e.to_string())?;
 
-    let config = SideloadConfiguration::new()
+    let mut config = SideloadConfiguration::new()
         .set_store_dir(store_dir.clone())
         .set_logger(&logger)
         .set_machine_name("CrossCode".to_string());
+    if !signing_entitlements.is_empty() {
+        config = config.set_entitlements(signing_entitlements);
+    }
 
     let provider = get_provider(&device).await?;
     sideload::sideload_app(&provider, &dev_session, app_path, config)
@@ -48,6 +116,223 @@ pub async fn sideload_app(
     Ok(())
 }
 
+static INSTALL_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+// Identity entitlements come from whoever signed the IPA before and are replaced when
+// it is re-signed, only capabilities are carried over
+const SIGNER_ENTITLEMENTS: &[&str] = &[
+    "application-identifier",
+    "com.apple.developer.team-identifier",
+    "get-task-allow",
+    "keychain-access-groups",
+    "beta-reports-active",
+];
+
+// Installs an IPA built elsewhere, or an older build, after re-signing it for the
+// signed in account. A new bundle ID can be given to stay under the free account App ID
+// limit or to install next to another copy.
+#[tauri::command]
+pub async fn install_ipa(
+    handle: tauri::AppHandle,
+    window: tauri::Window,
+    anisette_server: String,
+    device: DeviceInfo,
+    ipa_path: String,
+    bundle_id: Option<String>,
+) -> Result<(), String> {
+    // Unique per install so two running at once don't unpack over each other
+    let work_dir = std::env::temp_dir().join("crosscode").join(format!(
+        "InstallIPA-{}-{}",
+        std::process::id(),
+        INSTALL_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = install_ipa_internal(
+        &handle,
+        &window,
+        anisette_server,
+        device,
+        &PathBuf::from(&ipa_path),
+        bundle_id.filter(|id| !id.is_empty()),
+        &work_dir,
+    )
+    .await;
+    fs::remove_dir_all(&work_dir).ok();
+    match result {
+        Ok(()) => {
+            window.emit("build-output", "Install Success").ok();
+            Ok(())
+        }
+        Err(e) => crate::emit_error_and_return(&window, &e),
+    }
+}
+
+async fn install_ipa_internal(
+    handle: &tauri::AppHandle,
+    window: &tauri::Window,
+    anisette_server: String,
+    device: DeviceInfo,
+    ipa_path: &Path,
+    bundle_id: Option<String>,
+    work_dir: &Path,
+) -> Result<(), String> {
+    if work_dir.exists() {
+        fs::remove_dir_all(work_dir)
+            .map_err(|e| format!("Failed to clear {}: {}", work_dir.display(), e))?;
+    }
+    window
+        .emit(
+            "build-output",
+            format!("Unpacking {}...", ipa_path.display()),
+        )
+        .ok();
+    let file = File::open(ipa_path)
+        .map_err(|e| format!("Failed to open {}: {}", ipa_path.display(), e))?;
+    zip::ZipArchive::new(file)
+        .and_then(|mut archive| archive.extract(work_dir))
+        .map_err(|e| format!("Failed to unpack {}: {}", ipa_path.display(), e))?;
+
+    let app_path = fs::read_dir(work_dir.join("Payload"))
+        .map_err(|_| "The IPA has no Payload directory".to_string())?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .find(|path| path.extension().is_some_and(|ext| ext == "app"))
+        .ok_or("The IPA has no .app in its Payload directory".to_string())?;
+    // FairPlay encrypted binaries from the App Store can't run under another signature
+    if app_path.join("SC_Info").exists() {
+        return Err("App Store encrypted apps can't be re-signed".to_string());
+    }
+
+    let mut bundles = vec![app_path.clone()];
+    for dir in ["PlugIns", "Extensions"] {
+        if let Ok(entries) = fs::read_dir(app_path.join(dir)) {
+            bundles.extend(
+                entries
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.path())
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "appex")),
+            );
+        }
+    }
+    if app_path.join("Watch").exists() {
+        window
+            .emit(
+                "build-output",
+                "Warning: The Watch app will not be installed",
+            )
+            .ok();
+        fs::remove_dir_all(app_path.join("Watch"))
+            .map_err(|e| format!("Failed to remove Watch app: {}", e))?;
+    }
+
+    if let Some(new_bundle_id) = bundle_id {
+        let (old_bundle_id, _) = read_bundle_info(&app_path)?;
+        for bundle in &bundles {
+            let (current, _) = read_bundle_info(bundle)?;
+            // Extensions have to stay prefixed with the app's bundle ID
+            let renamed = match current.strip_prefix(&old_bundle_id) {
+                Some(suffix) => format!("{}{}", new_bundle_id, suffix),
+                None => format!(
+                    "{}.{}",
+                    new_bundle_id,
+                    current.rsplit('.').next().unwrap_or(&current)
+                ),
+            };
+            set_bundle_id(bundle, &renamed)?;
+        }
+        window
+            .emit(
+                "build-output",
+                format!("Changed bundle ID to {}", new_bundle_id),
+            )
+            .ok();
+    }
+
+    let mut entitlements = Vec::new();
+    for bundle in &bundles {
+        if let Some(bundle_entitlements) = read_signed_entitlements(bundle)? {
+            entitlements.push((bundle.clone(), bundle_entitlements));
+        }
+    }
+
+    sideload_app(
+        handle,
+        window,
+        anisette_server,
+        device,
+        app_path,
+        &entitlements,
+    )
+    .await
+    .map_err(|e| format!("Failed to sideload app: {}", e))
+}
+
+fn set_bundle_id(bundle_path: &Path, bundle_id: &str) -> Result<(), String> {
+    let info_path = bundle_path.join("Info.plist");
+    let mut info = Value::from_file(&info_path)
+        .map_err(|e| format!("Failed to read {}: {}", info_path.display(), e))?;
+    info.as_dictionary_mut()
+        .ok_or("Info.plist root is not a dictionary".to_string())?
+        .insert(
+            "CFBundleIdentifier".to_string(),
+            Value::String(bundle_id.to_string()),
+        );
+    info.to_file_xml(&info_path)
+        .map_err(|e| format!("Failed to write {}: {}", info_path.display(), e))
+}
+
+fn read_signed_entitlements(bundle_path: &Path) -> Result<Option<Dictionary>, String> {
+    let info = Value::from_file(bundle_path.join("Info.plist")).map_err(|e| {
+        format!(
+            "Failed to read Info.plist of {}: {}",
+            bundle_path.display(),
+            e
+        )
+    })?;
+    let executable = match info
+        .as_dictionary()
+        .and_then(|info| info.get("CFBundleExecutable"))
+        .and_then(Value::as_string)
+    {
+        Some(executable) => bundle_path.join(executable),
+        None => return Ok(None),
+    };
+    let entitlements = read_macho(&executable)?
+        .into_iter()
+        .find_map(|slice| slice.entitlements)
+        .map(|mut entitlements| {
+            for key in SIGNER_ENTITLEMENTS {
+                entitlements.remove(key);
+            }
+            entitlements
+        });
+    Ok(entitlements.filter(|e| !e.is_empty()))
+}
+
+fn read_bundle_info(bundle_path: &Path) -> Result<(String, String), String> {
+    let info = Value::from_file(bundle_path.join("Info.plist")).map_err(|e| {
+        format!(
+            "Failed to read Info.plist of {}: {}",
+            bundle_path.display(),
+            e
+        )
+    })?;
+    let info = info
+        .as_dictionary()
+        .ok_or("Info.plist root is not a dictionary".to_string())?;
+    let bundle_id = info
+        .get("CFBundleIdentifier")
+        .and_then(Value::as_string)
+        .ok_or(format!(
+            "{} is missing CFBundleIdentifier",
+            bundle_path.display()
+        ))?;
+    let name = info
+        .get("CFBundleName")
+        .and_then(Value::as_string)
+        .unwrap_or(bundle_id);
+    Ok((bundle_id.to_string(), name.to_string()))
+}
+
 #[tauri::command]
 pub async fn refresh_idevice(window: tauri::Window) {
     match list_devices().await {
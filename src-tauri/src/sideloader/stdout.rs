@@ -26,8 +26,18 @@ pub async fn start_stream_stdout(
     stream: State<'_, StdoutStream>,
     folder: String,
     anisette_server: String,
+    configuration: Option<String>,
+    app: Option<String>,
 ) -> Result<(), String> {
-    let bundle_id = get_bundle_id(&handle, &window, anisette_server, folder).await?;
+    let bundle_id = get_bundle_id(
+        &handle,
+        &window,
+        anisette_server,
+        folder,
+        configuration,
+        app,
+    )
+    .await?;
 
     let mut stream_guard = stream.lock().await;
     if let Some(token) = stream_guard.take() {
@@ -140,9 +150,11 @@ async fn get_bundle_id(
     window: &Window,
     anisette_server: String,
     folder: String,
+    configuration: Option<String>,
+    app: Option<String>,
 ) -> Result<String, String> {
     let config = TomlConfig::load_or_default(PathBuf::from(folder))?;
-    let bundle_id = config.project.bundle_id;
+    let bundle_id = config.bundle_id(configuration.as_deref(), app.as_deref())?;
 
     let session = get_developer_session(handle, window, anisette_server).await?;
 
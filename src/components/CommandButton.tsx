@@ -40,16 +40,27 @@ export default function CommandButton({
   shortcut,
   id,
 }: CommandButtonProps) {
-  const { isRunningCommand, currentCommand, runCommand, cancelCommand } =
-    useCommandRunner();
+  const {
+    isRunningCommand,
+    currentCommand,
+    currentJob,
+    runCommand,
+    cancelCommand,
+  } = useCommandRunner();
   const { setConsoleLines } = useIDE();
 
   const Component: React.ElementType = useMenuItem ? MenuItem : Button;
+  // The job keeps the project busy until it has actually stopped
+  const cancelling = currentJob?.state === "cancelling";
   const { addToast } = useToast();
 
   return (
     <Component
-      disabled={disabled || (isRunningCommand && currentCommand !== command)}
+      disabled={
+        disabled ||
+        cancelling ||
+        (isRunningCommand && currentCommand !== command)
+      }
       loading={
         useMenuItem ? undefined : isRunningCommand && currentCommand === command
       }
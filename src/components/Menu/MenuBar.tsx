@@ -18,6 +18,7 @@ import {
   CameraAlt,
 } from "@mui/icons-material";
 import { useParams } from "react-router-dom";
+import { invoke } from "@tauri-apps/api/core";
 import { Divider, Option, Select } from "@mui/joy";
 import { useIDE } from "../../utilities/IDEContext";
 import { useStore } from "../../utilities/StoreContext";
@@ -40,6 +41,10 @@ export default function MenuBar({ callbacks, editor }: MenuBarProps) {
     selectedToolchain,
     selectedDevice,
     setSelectedDevice,
+    selectedConfiguration,
+    setSelectedConfiguration,
+    selectedApp,
+    setSelectedApp,
     setScreenshot,
     mountDdi,
   } = useIDE();
@@ -48,6 +53,41 @@ export default function MenuBar({ callbacks, editor }: MenuBarProps) {
     "ani.sidestore.io"
   );
   const { addToast } = useToast();
+  const [configurations, setConfigurations] = useState<string[]>([]);
+  const [apps, setApps] = useState<string[]>([]);
+
+  const loadTargets = useCallback(async () => {
+    if (!path) return;
+    try {
+      setConfigurations(
+        await invoke<string[]>("list_configurations", { folder: path })
+      );
+      setApps(await invoke<string[]>("list_apps", { folder: path }));
+    } catch {
+      // A broken crosscode.toml is already reported by the project validation
+      setConfigurations([]);
+      setApps([]);
+    }
+  }, [path]);
+
+  useEffect(() => {
+    loadTargets();
+  }, [loadTargets]);
+
+  useEffect(() => {
+    if (
+      selectedConfiguration !== null &&
+      !configurations.includes(selectedConfiguration)
+    ) {
+      setSelectedConfiguration(null);
+    }
+  }, [configurations, selectedConfiguration]);
+
+  useEffect(() => {
+    if (selectedApp !== null && !apps.includes(selectedApp)) {
+      setSelectedApp(null);
+    }
+  }, [apps, selectedApp]);
 
   const updateScreenshot = useCallback(
     (data: number[]) => {
@@ -270,11 +310,58 @@ export default function MenuBar({ callbacks, editor }: MenuBarProps) {
         parameters={{
           folder: path,
           toolchainPath: selectedToolchain?.path ?? "",
-          debug: true,
+          build: {
+            debug: true,
+            configuration: selectedConfiguration,
+            app: selectedApp,
+          },
         }}
         tooltip="Build .ipa"
         sx={{ marginRight: 0 }}
       />
+      {apps.length > 0 && (
+        <Select
+          size="sm"
+          title="Select App"
+          value={selectedApp ?? ""}
+          onChange={(_, value) => {
+            setSelectedApp(value || null);
+          }}
+          onListboxOpenChange={(open) => {
+            if (open) loadTargets();
+          }}
+          sx={{ marginRight: "var(--padding-xs)" }}
+        >
+          <Option value="">Main App</Option>
+          {apps.map((app) => (
+            <Option key={app} value={app}>
+              {app}
+            </Option>
+          ))}
+        </Select>
+      )}
+      {configurations.length > 0 && (
+        <Select
+          size="sm"
+          title="Select Configuration"
+          value={selectedConfiguration ?? ""}
+          onChange={(_, value) => {
+            setSelectedConfiguration(value || null);
+          }}
+          onListboxOpenChange={(open) => {
+            // crosscode.toml may have been edited since the project was opened
+            if (open) loadTargets();
+          }}
+          sx={{ marginRight: "var(--padding-xs)" }}
+        >
+          <Option value="">Default</Option>
+          {configurations.map((configuration) => (
+            <Option key={configuration} value={configuration}>
+              {configuration}
+            </Option>
+          ))}
+        </Select>
+      )}
       <Divider orientation="vertical" />
       <div style={{ display: "flex", alignItems: "center" }}>
         <CommandButton
@@ -319,7 +406,11 @@ export default function MenuBar({ callbacks, editor }: MenuBarProps) {
             anisetteServer,
             device: selectedDevice,
             toolchainPath: selectedToolchain?.path ?? "",
-            debug: true,
+            build: {
+              debug: true,
+              configuration: selectedConfiguration,
+              app: selectedApp,
+            },
           }}
           validate={() => {
             if (!selectedDevice) {
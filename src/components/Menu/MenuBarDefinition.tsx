@@ -1,5 +1,5 @@
 import { openUrl } from "@tauri-apps/plugin-opener";
-import { MenuBarData } from "./MenuGroup";
+import { MenuBarData, MenuItem as MenuItemDefinition } from "./MenuGroup";
 import { WebviewWindow } from "@tauri-apps/api/webviewWindow";
 import { useParams } from "react-router-dom";
 import { useIDE } from "../../utilities/IDEContext";
@@ -12,6 +12,132 @@ import { restartServer } from "../../utilities/lsp-client";
 import { open } from "@tauri-apps/plugin-dialog";
 import { useContext } from "react";
 import { UpdateContext } from "../../utilities/UpdateContext";
+import { useCommandRunner } from "../../utilities/Command";
+import {
+  describeOutdated,
+  describePackage,
+  describeResolved,
+  OutdatedDependency,
+  ResolvedDependency,
+  SwiftPackage,
+} from "../../utilities/package";
+import {
+  CrashReport,
+  CrashReportEntry,
+  describeCrashReport,
+  describeCrashReports,
+} from "../../utilities/crashReports";
+
+type MachOReport = {
+  arch: string;
+  fileType: string;
+  uuid: string | null;
+  buildVersion: { platform: string; minOs: string; sdk: string } | null;
+  loadCommands: { name: string; size: number }[];
+  segments: {
+    name: string;
+    vmAddress: number;
+    vmSize: number;
+    fileOffset: number;
+    fileSize: number;
+  }[];
+  dylibs: { path: string; inSdk: boolean | null }[];
+  rpaths: string[];
+  codeSignature: {
+    size: number;
+    identifier: string | null;
+    teamId: string | null;
+    adhoc: boolean;
+  } | null;
+  entitlements: Record<string, unknown> | null;
+  symbolCount: number;
+};
+
+const describeMachO = (slice: MachOReport): string[] => {
+  const signature = slice.codeSignature
+    ? `${slice.codeSignature.adhoc ? "ad-hoc" : "signed"}${
+        slice.codeSignature.identifier
+          ? ` as ${slice.codeSignature.identifier}`
+          : ""
+      }${slice.codeSignature.teamId ? ` (team ${slice.codeSignature.teamId})` : ""}`
+    : "unsigned";
+  return [
+    `${slice.arch} ${slice.fileType}`,
+    `  UUID: ${slice.uuid ?? "none"}`,
+    `  Platform: ${
+      slice.buildVersion
+        ? `${slice.buildVersion.platform} ${slice.buildVersion.minOs}+ (SDK ${slice.buildVersion.sdk})`
+        : "unknown"
+    }`,
+    `  Code signature: ${signature}`,
+    `  Entitlements: ${
+      slice.entitlements ? Object.keys(slice.entitlements).join(", ") : "none"
+    }`,
+    `  Symbols: ${slice.symbolCount}`,
+    "  Linked libraries:",
+    ...slice.dylibs.map(
+      (dylib) =>
+        `    ${dylib.path}${
+          dylib.inSdk === false ? " (missing from the Darwin SDK)" : ""
+        }`
+    ),
+    "  Rpaths:",
+    ...slice.rpaths.map((rpath) => `    ${rpath}`),
+    "  Segments:",
+    ...slice.segments.map(
+      (segment) =>
+        `    ${segment.name || "(unnamed)"} vm 0x${segment.vmAddress.toString(
+          16
+        )} size ${segment.vmSize}, file size ${segment.fileSize}`
+    ),
+    "  Load commands:",
+    ...slice.loadCommands.map(
+      (command) => `    ${command.name} (${command.size} bytes)`
+    ),
+  ];
+};
+
+const bumpVersionItem = (
+  name: string,
+  id: string,
+  parameters: {
+    build: boolean;
+    bump?: "major" | "minor" | "patch" | "pre-release";
+  }
+): MenuItemDefinition => ({
+  name,
+  component: () => {
+    const { path } = useParams<"path">();
+    const { addToast } = useToast();
+    return (
+      <MenuItem
+        onClick={async () => {
+          try {
+            const version = await invoke<{
+              versionNum: string;
+              versionString: string;
+            }>("bump_version", {
+              folder: path,
+              build: parameters.build,
+              bump: parameters.bump ?? null,
+              preRelease: null,
+            });
+            addToast.success(
+              `Version is now ${version.versionString} (${version.versionNum})`
+            );
+          } catch (e) {
+            addToast.error(String(e));
+            console.error(e);
+          }
+        }}
+        id={id}
+      >
+        {name}
+      </MenuItem>
+    );
+  },
+  componentId: id,
+});
 
 export default [
   {
@@ -200,7 +326,8 @@ export default [
             shortcut: "Ctrl+B",
             component: ({ shortcut }) => {
               const { path } = useParams<"path">();
-              const { selectedToolchain } = useIDE();
+              const { selectedToolchain, selectedConfiguration, selectedApp } =
+                useIDE();
               return (
                 <CommandButton
                   shortcut={shortcut}
@@ -208,7 +335,11 @@ export default [
                   parameters={{
                     folder: path,
                     toolchainPath: selectedToolchain?.path ?? "",
-                    debug: true,
+                    build: {
+                      debug: true,
+                      configuration: selectedConfiguration,
+                      app: selectedApp,
+                    },
                   }}
                   label="Build .ipa (Debug)"
                   useMenuItem
@@ -223,7 +354,8 @@ export default [
             shortcut: "Ctrl+Shift+B",
             component: ({ shortcut }) => {
               const { path } = useParams<"path">();
-              const { selectedToolchain } = useIDE();
+              const { selectedToolchain, selectedConfiguration, selectedApp } =
+                useIDE();
               return (
                 <CommandButton
                   shortcut={shortcut}
@@ -231,7 +363,11 @@ export default [
                   parameters={{
                     folder: path,
                     toolchainPath: selectedToolchain?.path ?? "",
-                    debug: false,
+                    build: {
+                      debug: false,
+                      configuration: selectedConfiguration,
+                      app: selectedApp,
+                    },
                   }}
                   label="Build .ipa (Release)"
                   useMenuItem
@@ -241,12 +377,67 @@ export default [
             },
             componentId: "buildReleaseMenuBtn",
           },
+          {
+            name: "Build .app (Simulator)",
+            component: () => {
+              const { path } = useParams<"path">();
+              const { selectedToolchain, selectedConfiguration, selectedApp } =
+                useIDE();
+              return (
+                <CommandButton
+                  command="build_swift"
+                  parameters={{
+                    folder: path,
+                    toolchainPath: selectedToolchain?.path ?? "",
+                    build: {
+                      debug: true,
+                      configuration: selectedConfiguration,
+                      app: selectedApp,
+                      platform: "ios-simulator",
+                    },
+                  }}
+                  label="Build .app (Simulator)"
+                  useMenuItem
+                  id="buildSimulatorMenuBtn"
+                />
+              );
+            },
+            componentId: "buildSimulatorMenuBtn",
+          },
+          {
+            name: "Build .app (macOS)",
+            component: () => {
+              const { path } = useParams<"path">();
+              const { selectedToolchain, selectedConfiguration, selectedApp } =
+                useIDE();
+              return (
+                <CommandButton
+                  command="build_swift"
+                  parameters={{
+                    folder: path,
+                    toolchainPath: selectedToolchain?.path ?? "",
+                    build: {
+                      debug: true,
+                      configuration: selectedConfiguration,
+                      app: selectedApp,
+                      platform: "macos",
+                    },
+                  }}
+                  label="Build .app (macOS)"
+                  useMenuItem
+                  id="buildMacosMenuBtn"
+                />
+              );
+            },
+            componentId: "buildMacosMenuBtn",
+          },
           {
             name: "Build & Install",
             shortcut: "Ctrl+I",
             component: ({ selectedDevice, shortcut }) => {
               const { path } = useParams<"path">();
-              const { selectedToolchain } = useIDE();
+              const { selectedToolchain, selectedConfiguration, selectedApp } =
+                useIDE();
               const [anisetteServer] = useStore<string>(
                 "apple-id/anisette-server",
                 "ani.sidestore.io"
@@ -261,7 +452,11 @@ export default [
                     anisetteServer,
                     device: selectedDevice,
                     toolchainPath: selectedToolchain?.path ?? "",
-                    debug: true,
+                    build: {
+                      debug: true,
+                      configuration: selectedConfiguration,
+                      app: selectedApp,
+                    },
                   }}
                   label="Build & Install"
                   validate={() => {
@@ -278,6 +473,476 @@ export default [
             },
             componentId: "deployMenuBtn",
           },
+          {
+            name: "Install .ipa...",
+            component: ({ selectedDevice }) => {
+              const [anisetteServer] = useStore<string>(
+                "apple-id/anisette-server",
+                "ani.sidestore.io"
+              );
+              const { isRunningCommand, runCommand } = useCommandRunner();
+              const { setConsoleLines, promptText } = useIDE();
+              const { addToast } = useToast();
+              return (
+                <MenuItem
+                  disabled={isRunningCommand}
+                  onClick={async () => {
+                    if (!selectedDevice) {
+                      addToast.error("Please select a device to install to.");
+                      return;
+                    }
+                    const ipaPath = await open({
+                      title: "Select IPA",
+                      multiple: false,
+                      directory: false,
+                      filters: [{ name: "iOS Apps", extensions: ["ipa"] }],
+                    });
+                    if (!ipaPath) return;
+                    const bundleId = await promptText({
+                      title: "Bundle ID",
+                      message:
+                        "Optionally install under a different bundle ID, e.g. to keep it next to another copy. Leave empty to keep the original.",
+                      placeholder: "com.example.app",
+                      confirmLabel: "Install",
+                    });
+                    if (bundleId === null) return;
+                    setConsoleLines([]);
+                    runCommand("install_ipa", {
+                      anisetteServer,
+                      device: selectedDevice,
+                      ipaPath,
+                      bundleId: bundleId.trim() || null,
+                    }).catch((e) => {
+                      addToast.error(e);
+                      console.error(e);
+                    });
+                  }}
+                  id="installIpaMenuBtn"
+                >
+                  Install .ipa...
+                </MenuItem>
+              );
+            },
+            componentId: "installIpaMenuBtn",
+          },
+          {
+            name: "Validate .app...",
+            component: () => {
+              const { setConsoleLines } = useIDE();
+              const { addToast } = useToast();
+              return (
+                <MenuItem
+                  onClick={async () => {
+                    const appPath = await open({
+                      title: "Select .app bundle",
+                      multiple: false,
+                      directory: true,
+                    });
+                    if (!appPath) return;
+                    try {
+                      const issues = await invoke<
+                        {
+                          severity: "error" | "warning";
+                          file: string | null;
+                          message: string;
+                        }[]
+                      >("validate_app_bundle", { path: appPath });
+                      setConsoleLines(
+                        issues.map(
+                          (issue) =>
+                            `${
+                              issue.severity === "error" ? "Error" : "Warning"
+                            }: ${issue.file ? issue.file + ": " : ""}${
+                              issue.message
+                            }`
+                        )
+                      );
+                      const errors = issues.filter(
+                        (issue) => issue.severity === "error"
+                      ).length;
+                      if (errors > 0) {
+                        addToast.error(`Found ${errors} problem(s) in the bundle`);
+                      } else {
+                        addToast.success("No problems found in the bundle");
+                      }
+                    } catch (e) {
+                      addToast.error(String(e));
+                      console.error(e);
+                    }
+                  }}
+                  id="validateAppMenuBtn"
+                >
+                  Validate .app...
+                </MenuItem>
+              );
+            },
+            componentId: "validateAppMenuBtn",
+          },
+          {
+            name: "Inspect Binary...",
+            component: () => {
+              const { setConsoleLines, selectedToolchain } = useIDE();
+              const { addToast } = useToast();
+              return (
+                <MenuItem
+                  onClick={async () => {
+                    const binaryPath = await open({
+                      title: "Select Mach-O binary",
+                      multiple: false,
+                      directory: false,
+                    });
+                    if (!binaryPath) return;
+                    try {
+                      const slices = await invoke<MachOReport[]>(
+                        "inspect_macho",
+                        {
+                          path: binaryPath,
+                          toolchainPath: selectedToolchain?.path ?? null,
+                        }
+                      );
+                      setConsoleLines(slices.flatMap(describeMachO));
+                    } catch (e) {
+                      addToast.error(String(e));
+                      console.error(e);
+                    }
+                  }}
+                  id="inspectBinaryMenuBtn"
+                >
+                  Inspect Binary...
+                </MenuItem>
+              );
+            },
+            componentId: "inspectBinaryMenuBtn",
+          },
+        ],
+      },
+      {
+        label: "Package",
+        items: [
+          {
+            name: "Show Package Graph",
+            component: () => {
+              const { setConsoleLines, selectedToolchain } = useIDE();
+              const { path } = useParams<"path">();
+              const { addToast } = useToast();
+              return (
+                <MenuItem
+                  onClick={async () => {
+                    try {
+                      const pkg = await invoke<SwiftPackage>("dump_package", {
+                        folder: path,
+                        toolchainPath: selectedToolchain?.path ?? "",
+                      });
+                      setConsoleLines(describePackage(pkg));
+                    } catch (e) {
+                      addToast.error(String(e));
+                      console.error(e);
+                    }
+                  }}
+                  id="showPackageMenuBtn"
+                >
+                  Show Package Graph
+                </MenuItem>
+              );
+            },
+            componentId: "showPackageMenuBtn",
+          },
+          {
+            name: "Show Resolved Packages",
+            component: () => {
+              const { setConsoleLines } = useIDE();
+              const { path } = useParams<"path">();
+              const { addToast } = useToast();
+              return (
+                <MenuItem
+                  onClick={async () => {
+                    try {
+                      const pins = await invoke<ResolvedDependency[]>(
+                        "list_dependencies",
+                        { folder: path }
+                      );
+                      setConsoleLines(describeResolved(pins));
+                    } catch (e) {
+                      addToast.error(String(e));
+                      console.error(e);
+                    }
+                  }}
+                  id="showResolvedMenuBtn"
+                >
+                  Show Resolved Packages
+                </MenuItem>
+              );
+            },
+            componentId: "showResolvedMenuBtn",
+          },
+          {
+            name: "Resolve Packages",
+            component: () => {
+              const { path } = useParams<"path">();
+              const { selectedToolchain } = useIDE();
+              return (
+                <CommandButton
+                  command="resolve_packages"
+                  parameters={{
+                    folder: path,
+                    toolchainPath: selectedToolchain?.path ?? "",
+                  }}
+                  label="Resolve Packages"
+                  useMenuItem
+                  id="resolvePackagesMenuBtn"
+                />
+              );
+            },
+            componentId: "resolvePackagesMenuBtn",
+          },
+          {
+            name: "Update Packages",
+            component: () => {
+              const { path } = useParams<"path">();
+              const { selectedToolchain } = useIDE();
+              return (
+                <CommandButton
+                  command="update_packages"
+                  parameters={{
+                    folder: path,
+                    toolchainPath: selectedToolchain?.path ?? "",
+                    packages: [],
+                  }}
+                  label="Update Packages"
+                  useMenuItem
+                  id="updatePackagesMenuBtn"
+                />
+              );
+            },
+            componentId: "updatePackagesMenuBtn",
+          },
+          {
+            name: "Check for Package Updates",
+            component: () => {
+              const { setConsoleLines, selectedToolchain } = useIDE();
+              const { path } = useParams<"path">();
+              const { addToast } = useToast();
+              return (
+                <MenuItem
+                  onClick={async () => {
+                    try {
+                      setConsoleLines(["Checking for package updates..."]);
+                      const outdated = await invoke<OutdatedDependency[]>(
+                        "check_outdated_dependencies",
+                        {
+                          folder: path,
+                          toolchainPath: selectedToolchain?.path ?? "",
+                        }
+                      );
+                      setConsoleLines(describeOutdated(outdated));
+                    } catch (e) {
+                      addToast.error(String(e));
+                      console.error(e);
+                    }
+                  }}
+                  id="checkPackageUpdatesMenuBtn"
+                >
+                  Check for Package Updates
+                </MenuItem>
+              );
+            },
+            componentId: "checkPackageUpdatesMenuBtn",
+          },
+          {
+            name: "Add Local Package...",
+            component: () => {
+              const { selectedToolchain } = useIDE();
+              const { path } = useParams<"path">();
+              const { addToast } = useToast();
+              return (
+                <MenuItem
+                  onClick={async () => {
+                    const packagePath = await open({
+                      title: "Select Package Folder",
+                      multiple: false,
+                      directory: true,
+                    });
+                    if (!packagePath) return;
+                    try {
+                      await invoke("add_dependency", {
+                        folder: path,
+                        toolchainPath: selectedToolchain?.path ?? "",
+                        location: packagePath,
+                        requirement: null,
+                      });
+                      addToast.success("Added package to Package.swift");
+                    } catch (e) {
+                      addToast.error(String(e));
+                      console.error(e);
+                    }
+                  }}
+                  id="addLocalPackageMenuBtn"
+                >
+                  Add Local Package...
+                </MenuItem>
+              );
+            },
+            componentId: "addLocalPackageMenuBtn",
+          },
+        ],
+      },
+      {
+        label: "Version",
+        items: [
+          bumpVersionItem("Increment Build Number", "bumpBuildMenuBtn", {
+            build: true,
+          }),
+          bumpVersionItem("Bump Patch Version", "bumpPatchMenuBtn", {
+            build: true,
+            bump: "patch",
+          }),
+          bumpVersionItem("Bump Minor Version", "bumpMinorMenuBtn", {
+            build: true,
+            bump: "minor",
+          }),
+          bumpVersionItem("Bump Major Version", "bumpMajorMenuBtn", {
+            build: true,
+            bump: "major",
+          }),
+          bumpVersionItem("Bump Pre-release", "bumpPreReleaseMenuBtn", {
+            build: true,
+            bump: "pre-release",
+          }),
+        ],
+      },
+      {
+        label: "Crash Reports",
+        items: [
+          {
+            name: "Show Device Crash Reports",
+            component: ({ selectedDevice }) => {
+              const { setConsoleLines, selectedToolchain } = useIDE();
+              const { path } = useParams<"path">();
+              const { addToast } = useToast();
+              return (
+                <MenuItem
+                  onClick={async () => {
+                    if (!selectedDevice) {
+                      addToast.error("Please select a device first.");
+                      return;
+                    }
+                    try {
+                      const reports = await invoke<CrashReportEntry[]>(
+                        "list_crash_reports",
+                        {
+                          device: selectedDevice,
+                          folder: path,
+                          toolchainPath: selectedToolchain?.path ?? "",
+                        }
+                      );
+                      setConsoleLines(describeCrashReports(reports));
+                    } catch (e) {
+                      addToast.error(String(e));
+                      console.error(e);
+                    }
+                  }}
+                  id="listCrashReportsMenuBtn"
+                >
+                  Show Device Crash Reports
+                </MenuItem>
+              );
+            },
+            componentId: "listCrashReportsMenuBtn",
+          },
+          {
+            name: "Pull Crash Report...",
+            component: ({ selectedDevice }) => {
+              const { setConsoleLines, selectedToolchain, promptText } =
+                useIDE();
+              const { path } = useParams<"path">();
+              const { addToast } = useToast();
+              return (
+                <MenuItem
+                  onClick={async () => {
+                    if (!selectedDevice) {
+                      addToast.error("Please select a device first.");
+                      return;
+                    }
+                    const toolchainPath = selectedToolchain?.path ?? "";
+                    try {
+                      const reports = await invoke<CrashReportEntry[]>(
+                        "list_crash_reports",
+                        { device: selectedDevice, folder: path, toolchainPath }
+                      );
+                      if (reports.length === 0) {
+                        addToast.info("No crash reports for this project");
+                        return;
+                      }
+                      // Newest first, leaving the name empty pulls that one
+                      const name = await promptText({
+                        title: "Pull Crash Report",
+                        message: `${reports.length} report(s) on ${selectedDevice.name}, the newest is ${reports[0].name}. Leave empty to pull it, or enter the name of another one from Show Device Crash Reports.`,
+                        placeholder: reports[0].name,
+                        confirmLabel: "Pull",
+                      });
+                      if (name === null) return;
+                      const report = await invoke<CrashReport>(
+                        "pull_crash_report",
+                        {
+                          device: selectedDevice,
+                          folder: path,
+                          toolchainPath,
+                          name: name.trim() || reports[0].name,
+                        }
+                      );
+                      setConsoleLines(describeCrashReport(report));
+                    } catch (e) {
+                      addToast.error(String(e));
+                      console.error(e);
+                    }
+                  }}
+                  id="pullCrashReportMenuBtn"
+                >
+                  Pull Crash Report...
+                </MenuItem>
+              );
+            },
+            componentId: "pullCrashReportMenuBtn",
+          },
+          {
+            name: "Open Crash Report...",
+            component: () => {
+              const { setConsoleLines, selectedToolchain } = useIDE();
+              const { path } = useParams<"path">();
+              const { addToast } = useToast();
+              return (
+                <MenuItem
+                  onClick={async () => {
+                    const reportPath = await open({
+                      title: "Select crash report",
+                      multiple: false,
+                      directory: false,
+                      filters: [{ name: "Crash Reports", extensions: ["ips"] }],
+                    });
+                    if (!reportPath) return;
+                    try {
+                      const report = await invoke<CrashReport>(
+                        "open_crash_report",
+                        {
+                          folder: path,
+                          toolchainPath: selectedToolchain?.path ?? "",
+                          path: reportPath,
+                        }
+                      );
+                      setConsoleLines(describeCrashReport(report));
+                    } catch (e) {
+                      addToast.error(String(e));
+                      console.error(e);
+                    }
+                  }}
+                  id="openCrashReportMenuBtn"
+                >
+                  Open Crash Report...
+                </MenuItem>
+              );
+            },
+            componentId: "openCrashReportMenuBtn",
+          },
         ],
       },
       {
@@ -9,14 +9,19 @@ import { invoke } from "@tauri-apps/api/core";
 import CommandConsole from "./CommandConsole";
 import Console from "./Console";
 import FilteredConsole, { FilteredConsoleHandle } from "./FilteredConsole";
+import Problems from "./Problems";
 import { useParams } from "react-router";
 import { useStore } from "../../utilities/StoreContext";
 
-const staticTabs = [
+const createStaticTabs = (openFile: (file: string) => void) => [
   {
     name: "Build Output",
     component: <CommandConsole />,
   },
+  {
+    name: "Problems",
+    component: <Problems openFile={openFile} />,
+  },
   {
     name: "SourceKit-LSP",
     component: (
@@ -25,7 +30,12 @@ const staticTabs = [
   },
 ];
 
-export default function BottomBar() {
+export default function BottomBar({
+  openFile,
+}: {
+  openFile: (file: string) => void;
+}) {
+  const staticTabs = createStaticTabs(openFile);
   const [focused, setFocused] = useState<number>();
   const [refreshSyslog, setRefreshSyslog] = useState<number>(0);
   const [runningSyslog, setRunningSyslog] = useState<boolean>(false);
@@ -197,7 +207,8 @@ function BottomBarFilter({
   customTooltip: string;
   requiresDDI?: boolean;
 }) {
-  const { selectedDevice, mountDdi } = useIDE();
+  const { selectedDevice, selectedConfiguration, selectedApp, mountDdi } =
+    useIDE();
   const { addToast } = useToast();
   const { path } = useParams<"path">();
   const [anisetteServer] = useStore<string>(
@@ -230,6 +241,8 @@ function BottomBarFilter({
             device: selectedDevice,
             folder: path ?? "",
             anisetteServer: anisetteServer,
+            configuration: selectedConfiguration,
+            app: selectedApp,
           }}
           validate={() => {
             if (!selectedDevice) {
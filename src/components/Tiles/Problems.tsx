@@ -0,0 +1,145 @@
+import { useEffect, useState } from "react";
+import "./Console.css";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import { Virtuoso } from "react-virtuoso";
+import { useParams } from "react-router";
+import { Typography } from "@mui/joy";
+import { useToast } from "react-toast-plus";
+import { BuildJob } from "../../utilities/Command";
+
+export type DiagnosticSeverity = "error" | "warning" | "note" | "remark";
+
+export interface Diagnostic {
+  severity: DiagnosticSeverity;
+  // The tool that reported it when there is no source location, e.g. "ld64.lld"
+  tool: string | null;
+  file: string | null;
+  range: {
+    startLine: number;
+    startColumn: number;
+    endLine: number;
+    endColumn: number;
+  } | null;
+  message: string;
+  fixIts: string[];
+  notes: Diagnostic[];
+}
+
+const severityColors: Record<
+  DiagnosticSeverity,
+  "danger" | "warning" | "neutral"
+> = {
+  error: "danger",
+  warning: "warning",
+  note: "neutral",
+  remark: "neutral",
+};
+
+export default function Problems({
+  openFile,
+}: {
+  openFile: (file: string) => void;
+}) {
+  const [diagnostics, setDiagnostics] = useState<Diagnostic[]>([]);
+  const { path } = useParams<"path">();
+  const { addToast } = useToast();
+
+  useEffect(() => {
+    setDiagnostics([]);
+  }, [path]);
+
+  useEffect(() => {
+    const label = getCurrentWindow().label;
+    const unlistenDiagnostic = listen<Diagnostic>(
+      "build-diagnostic",
+      (event) => {
+        setDiagnostics((diagnostics) => [...diagnostics, event.payload]);
+      }
+    );
+    // Only the problems of the latest build are shown
+    const unlistenJob = listen<BuildJob>("build-job", (event) => {
+      if (
+        event.payload.window === label &&
+        event.payload.state === "running"
+      ) {
+        setDiagnostics([]);
+      }
+    });
+    return () => {
+      unlistenDiagnostic.then((fn) => fn());
+      unlistenJob.then((fn) => fn());
+    };
+  }, []);
+
+  const location = (diagnostic: Diagnostic) => {
+    if (!diagnostic.file) return diagnostic.tool ?? "";
+    let file = diagnostic.file;
+    if (path && file.startsWith(path)) {
+      file = file.slice(path.length).replace(/^[\\/]/, "");
+    }
+    if (!diagnostic.range) return file;
+    return `${file}:${diagnostic.range.startLine}:${diagnostic.range.startColumn}`;
+  };
+
+  const open = async (diagnostic: Diagnostic) => {
+    if (!diagnostic.file) return;
+    try {
+      // Paths from a build in WSL need to be translated on Windows
+      openFile(
+        await invoke<string>("windows_path", { path: diagnostic.file })
+      );
+    } catch (e) {
+      addToast.error(`Failed to open ${diagnostic.file}: ${e}`);
+    }
+  };
+
+  if (diagnostics.length === 0) {
+    return (
+      <div className="console-container">
+        <Typography level="body-sm" sx={{ paddingTop: "var(--padding-xs)" }}>
+          No problems in the last build
+        </Typography>
+      </div>
+    );
+  }
+
+  return (
+    <div className="console-container">
+      <Virtuoso
+        className="console-tile"
+        data={diagnostics}
+        itemContent={(_, diagnostic) => (
+          <div
+            style={{
+              cursor: diagnostic.file ? "pointer" : "default",
+              padding: "2px 0",
+            }}
+            onClick={() => open(diagnostic)}
+            title={diagnostic.notes.map((note) => note.message).join("\n")}
+          >
+            <Typography
+              level="body-sm"
+              color={severityColors[diagnostic.severity]}
+              component="span"
+              sx={{ fontWeight: "bold", marginRight: "var(--padding-xs)" }}
+            >
+              {diagnostic.severity}
+            </Typography>
+            <Typography level="body-sm" component="span">
+              {diagnostic.message}
+            </Typography>
+            <Typography
+              level="body-xs"
+              component="span"
+              sx={{ marginLeft: "var(--padding-xs)" }}
+            >
+              {location(diagnostic)}
+            </Typography>
+          </div>
+        )}
+      />
+    </div>
+  );
+}
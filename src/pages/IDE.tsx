@@ -30,12 +30,37 @@ import { writeFile } from "@tauri-apps/plugin-fs";
 
 export interface IDEProps {}
 
-type ProjectValidation =
-  | "Valid"
-  | "Invalid"
-  | "UnsupportedFormatVersion"
-  | "InvalidPackage"
-  | "InvalidToolchain";
+interface ConfigMigration {
+  fromVersion: number;
+  toVersion: number;
+  diff: string;
+  backupPath: string | null;
+}
+
+type FixAction =
+  | { type: "createConfig"; bundleId: string }
+  | { type: "migrateConfig" }
+  | { type: "setProduct"; product: string }
+  | { type: "addIosPlatform"; version: string };
+
+interface ValidationFinding {
+  kind:
+    | "MissingProject"
+    | "MissingPackage"
+    | "MissingConfig"
+    | "InvalidConfig"
+    | "UnsupportedFormatVersion"
+    | "OutdatedFormatVersion"
+    | "InvalidToolchain"
+    | "InvalidPackage"
+    | "MissingExecutable"
+    | "MissingPlatform";
+  severity: "error" | "warning";
+  message: string;
+  file: string | null;
+  line: number | null;
+  fix: { description: string; action: FixAction } | null;
+}
 
 let autoStartedLsp = "";
 
@@ -80,8 +105,9 @@ export default () => {
     Record<string, (() => void) | (() => Promise<void>)>
   >({});
   const navigate = useNavigate();
-  const [projectValidation, setProjectValidation] =
-    useState<ProjectValidation | null>(null);
+  const [findings, setFindings] = useState<ValidationFinding[] | null>(null);
+  const [validationCount, setValidationCount] = useState(0);
+  const [migration, setMigration] = useState<ConfigMigration | null>(null);
   const [editor, setEditor] = useState<IStandaloneCodeEditor | null>(null);
   const { addToast } = useToast();
 
@@ -111,20 +137,57 @@ export default () => {
       !initialized
     )
       return;
-    setProjectValidation(null);
+    setFindings(null);
     (async () => {
       if (path) {
         const toolchainPath = selectedToolchain?.path ?? "";
-        const validation = await invoke<ProjectValidation>("validate_project", {
-          projectPath: path,
-          toolchainPath: toolchainPath,
-        });
+        const validation = await invoke<ValidationFinding[]>(
+          "validate_project",
+          {
+            projectPath: path,
+            toolchainPath: toolchainPath,
+          }
+        );
         if (validation) {
-          setProjectValidation(validation);
+          setFindings(validation);
         }
       }
     })();
-  }, [path, selectedToolchain, initialized]);
+  }, [path, selectedToolchain, initialized, validationCount]);
+
+  const hasFinding = (kind: ValidationFinding["kind"]) =>
+    findings?.some((finding) => finding.kind === kind) ?? false;
+  const needsMigration = hasFinding("OutdatedFormatVersion");
+  const hasErrors =
+    findings?.some((finding) => finding.severity === "error") ?? false;
+
+  useEffect(() => {
+    setMigration(null);
+    if (!needsMigration) return;
+    // Dry run, so the changes can be reviewed before upgrading
+    invoke<ConfigMigration>("preview_config_migration", { folder: path })
+      .then(setMigration)
+      .catch((e) => {
+        addToast.error(`Failed to preview migration: ${e}`);
+      });
+  }, [needsMigration, path]);
+
+  const applyFix = useCallback(
+    async (action: FixAction) => {
+      try {
+        const result = await invoke<string>("apply_validation_fix", {
+          projectPath: path,
+          toolchainPath: selectedToolchain?.path ?? "",
+          action,
+        });
+        addToast.success(result);
+        setValidationCount((count) => count + 1);
+      } catch (e) {
+        addToast.error(`Failed to apply fix: ${e}`);
+      }
+    },
+    [path, selectedToolchain, addToast]
+  );
 
   useEffect(() => {
     if (openFiles.length === 0) {
@@ -229,7 +292,7 @@ export default () => {
             openNewFile={openNewFile}
             setEditorUpper={setEditor}
           />
-          <BottomBar />
+          <BottomBar openFile={openNewFile} />
         </Splitter>
         {screenshot && (
           <div className="screenshot-tile">
@@ -268,12 +331,12 @@ export default () => {
       </Splitter>
       {initialized &&
         selectedToolchain !== null &&
-        projectValidation !== null &&
-        projectValidation !== "Valid" && (
+        findings !== null &&
+        findings.length > 0 && (
           <Modal
             open={true}
             onClose={() => {
-              setProjectValidation(null);
+              setFindings(null);
             }}
           >
             <ModalDialog sx={{ maxWidth: "90vw" }}>
@@ -281,20 +344,78 @@ export default () => {
               <div>
                 <div style={{ display: "flex", gap: "var(--padding-sm)" }}>
                   <div style={{ width: "1.25rem" }}>
-                    <ErrorIcon />
+                    {hasErrors ? <ErrorIcon /> : <WarningIcon />}
                   </div>
-                  <Typography level="h3">Failed to load project</Typography>
+                  <Typography level="h3">
+                    {needsMigration
+                      ? "Project needs upgrading"
+                      : hasErrors
+                      ? "Failed to load project"
+                      : "Project has problems"}
+                  </Typography>
                 </div>
                 <Typography level="body-lg">
-                  {getValidationMsg(projectValidation)} Some features may not
-                  work as expected.
+                  Some features may not work as expected until these are
+                  resolved.
                 </Typography>
+                <div className="validation-findings">
+                  {findings.map((finding, i) => (
+                    <div key={i} className="validation-finding">
+                      <div className="validation-finding-message">
+                        <Typography
+                          level="body-md"
+                          color={
+                            finding.severity === "error" ? "danger" : "warning"
+                          }
+                        >
+                          {finding.file &&
+                            `${finding.file}${
+                              finding.line !== null ? `:${finding.line}` : ""
+                            }: `}
+                          {finding.message}
+                        </Typography>
+                      </div>
+                      {finding.fix && (
+                        <Button
+                          size="sm"
+                          variant="soft"
+                          disabled={
+                            finding.kind === "OutdatedFormatVersion" &&
+                            !migration
+                          }
+                          onClick={() => applyFix(finding.fix!.action)}
+                        >
+                          {finding.fix.description}
+                        </Button>
+                      )}
+                    </div>
+                  ))}
+                </div>
+                {migration && (
+                  <pre className="config-migration-diff">
+                    {migration.diff.split("\n").map((line, i) => (
+                      <div
+                        key={i}
+                        className={
+                          line.startsWith("+")
+                            ? "diff-added"
+                            : line.startsWith("-")
+                            ? "diff-removed"
+                            : undefined
+                        }
+                      >
+                        {line}
+                      </div>
+                    ))}
+                  </pre>
+                )}
               </div>
 
               <Divider sx={{ mb: "var(--padding-xs)" }} />
               <div style={{ display: "flex", gap: "var(--padding-lg)" }}>
-                {projectValidation === "InvalidToolchain" && <SwiftMenu />}
-                {projectValidation !== "InvalidToolchain" && (
+                {hasFinding("InvalidToolchain") && <SwiftMenu />}
+                {(hasFinding("MissingProject") ||
+                  hasFinding("MissingPackage")) && (
                   <>
                     <Button
                       onClick={() => {
@@ -306,16 +427,16 @@ export default () => {
                     <Button onClick={openFolderDialog}>
                       Open Other Project
                     </Button>
-                    <Button
-                      onClick={() => {
-                        setProjectValidation(null);
-                      }}
-                      variant="outlined"
-                    >
-                      Ignore
-                    </Button>
                   </>
                 )}
+                <Button
+                  onClick={() => {
+                    setFindings(null);
+                  }}
+                  variant="outlined"
+                >
+                  Ignore
+                </Button>
               </div>
             </ModalDialog>
           </Modal>
@@ -438,18 +559,3 @@ export default () => {
     </div>
   );
 };
-
-function getValidationMsg(validation: ProjectValidation): string {
-  switch (validation) {
-    case "Invalid":
-      return "This does not appear to be a valid CrossCode project.";
-    case "InvalidPackage":
-      return "SwiftPM was unable to parse your package. Please check your Package.swift file.";
-    case "UnsupportedFormatVersion":
-      return "This project uses an unsupported config format version. You may need to update CrossCode.";
-    case "InvalidToolchain":
-      return "Your Swift toolchain appears to be invalid.";
-    default:
-      return "";
-  }
-}
@@ -1,13 +1,31 @@
 import { Mutex } from "async-mutex";
 import { invoke } from "@tauri-apps/api/core";
-import { useState, createContext, useContext } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import { useState, createContext, useContext, useEffect } from "react";
 
 const commandRunnerMutex = new Mutex();
 
+export type BuildJobState =
+  | "running"
+  | "cancelling"
+  | "succeeded"
+  | "failed"
+  | "cancelled";
+
+export interface BuildJob {
+  id: number;
+  window: string;
+  project: string;
+  kind: string;
+  state: BuildJobState;
+}
+
 // Create a context for sharing command state
 const CommandContext = createContext<{
   isRunningCommand: boolean;
   currentCommand: string | null;
+  currentJob: BuildJob | null;
   setIsRunningCommand: React.Dispatch<React.SetStateAction<boolean>>;
   setCurrentCommand: React.Dispatch<React.SetStateAction<string | null>>;
 } | null>(null);
@@ -15,12 +33,31 @@ const CommandContext = createContext<{
 export function CommandProvider({ children }: { children: React.ReactNode }) {
   const [isRunningCommand, setIsRunningCommand] = useState(false);
   const [currentCommand, setCurrentCommand] = useState<string | null>(null);
+  const [currentJob, setCurrentJob] = useState<BuildJob | null>(null);
+
+  useEffect(() => {
+    // Jobs are reported to every window, only track the ones started from this one
+    const label = getCurrentWindow().label;
+    const unlisten = listen<BuildJob>("build-job", (event) => {
+      const job = event.payload;
+      if (job.window !== label) return;
+      if (job.state === "running" || job.state === "cancelling") {
+        setCurrentJob(job);
+      } else {
+        setCurrentJob((current) => (current?.id === job.id ? null : current));
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
 
   return (
     <CommandContext.Provider
       value={{
         isRunningCommand,
         currentCommand,
+        currentJob,
         setIsRunningCommand,
         setCurrentCommand,
       }}
@@ -40,6 +77,7 @@ export function useCommandRunner() {
   const {
     isRunningCommand,
     currentCommand,
+    currentJob,
     setIsRunningCommand,
     setCurrentCommand,
   } = context;
@@ -72,17 +110,21 @@ export function useCommandRunner() {
     } finally {
       setIsRunningCommand(true);
       setCurrentCommand(null);
-      // try {
-      //   await invoke("cancel_command");
-      // } finally {
-      setIsRunningCommand(false);
+      try {
+        // The command's promise settles once the job has stopped
+        if (currentJob) {
+          await invoke("cancel_build", { jobId: currentJob.id });
+        }
+      } finally {
+        setIsRunningCommand(false);
+      }
     }
-    //}
   };
 
   return {
     isRunningCommand,
     currentCommand,
+    currentJob,
     runCommand,
     cancelCommand,
   };
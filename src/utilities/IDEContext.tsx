@@ -31,6 +31,13 @@ import { isCompatable } from "../components/SwiftMenu";
 
 let isMainWindow = getCurrentWindow().label === "main";
 
+export type TextPrompt = {
+  title: string;
+  message: string;
+  placeholder?: string;
+  confirmLabel?: string;
+};
+
 export interface IDEContextType {
   initialized: boolean;
   ready: boolean | null;
@@ -57,9 +64,16 @@ export interface IDEContextType {
   ) => void;
   selectedDevice: DeviceInfo | null;
   setSelectedDevice: React.Dispatch<React.SetStateAction<DeviceInfo | null>>;
+  // A named configuration from crosscode.toml, null builds the plain project
+  selectedConfiguration: string | null;
+  setSelectedConfiguration: React.Dispatch<React.SetStateAction<string | null>>;
+  // The product of one of the [[apps]] in crosscode.toml, null builds the main app
+  selectedApp: string | null;
+  setSelectedApp: React.Dispatch<React.SetStateAction<string | null>>;
   mountDdi: (ask: boolean) => Promise<boolean>;
   setScreenshot: React.Dispatch<React.SetStateAction<string | null>>;
   screenshot: string | null;
+  promptText: (prompt: TextPrompt) => Promise<string | null>;
 }
 
 export type DeviceInfo = {
@@ -116,10 +130,35 @@ export const IDEProvider: React.FC<{
   const [hasLimitedRam, setHasLimitedRam] = useState<boolean>(false);
 
   const [selectedDevice, setSelectedDevice] = useState<DeviceInfo | null>(null);
+  const [selectedConfiguration, setSelectedConfiguration] = useState<
+    string | null
+  >(null);
+  const [selectedApp, setSelectedApp] = useState<string | null>(null);
 
   const [ddiOpen, setDdiOpen] = useState(false);
   const [ddiProgress, setDdiProgress] = useState(0);
   const [screenshot, setScreenshot] = useState<string | null>(null);
+  const [textPrompt, setTextPrompt] = useState<
+    (TextPrompt & { resolve: (value: string | null) => void }) | null
+  >(null);
+  const textPromptInput = useRef<HTMLInputElement | null>(null);
+
+  // Resolves with what was entered, or null if the prompt was cancelled
+  const promptText = useCallback(
+    (prompt: TextPrompt) =>
+      new Promise<string | null>((resolve) => {
+        setTextPrompt({ ...prompt, resolve });
+      }),
+    []
+  );
+
+  const closeTextPrompt = useCallback(
+    (value: string | null) => {
+      textPrompt?.resolve(value);
+      setTextPrompt(null);
+    },
+    [textPrompt]
+  );
 
   const { checkForUpdates } = useContext(UpdateContext);
   const { store, storeInitialized } = useContext(StoreContext);
@@ -510,11 +549,16 @@ export const IDEProvider: React.FC<{
       hasLimitedRam,
       selectedDevice,
       setSelectedDevice,
+      selectedConfiguration,
+      setSelectedConfiguration,
+      selectedApp,
+      setSelectedApp,
       mountDdi,
       ready,
       darwinSDKVersion,
       screenshot,
       setScreenshot,
+      promptText,
     }),
     [
       isWindows,
@@ -535,11 +579,16 @@ export const IDEProvider: React.FC<{
       hasLimitedRam,
       selectedDevice,
       setSelectedDevice,
+      selectedConfiguration,
+      setSelectedConfiguration,
+      selectedApp,
+      setSelectedApp,
       mountDdi,
       ready,
       darwinSDKVersion,
       screenshot,
       setScreenshot,
+      promptText,
     ]
   );
 
@@ -687,6 +736,48 @@ export const IDEProvider: React.FC<{
           </Typography>
         </ModalDialog>
       </Modal>
+      <Modal open={textPrompt !== null} onClose={() => closeTextPrompt(null)}>
+        <ModalDialog>
+          <Typography level="h4">{textPrompt?.title}</Typography>
+          <Typography level="body-md">{textPrompt?.message}</Typography>
+          <form
+            onSubmit={(e) => {
+              e.preventDefault();
+              closeTextPrompt(textPromptInput.current?.value ?? "");
+            }}
+          >
+            <Input
+              autoFocus
+              type="text"
+              slotProps={{ input: { ref: textPromptInput } }}
+              placeholder={textPrompt?.placeholder}
+            />
+            <Button
+              variant="soft"
+              sx={{
+                margin: "var(--padding-md) 0",
+                width: "100%",
+                marginBottom: "0",
+              }}
+              type="submit"
+            >
+              {textPrompt?.confirmLabel ?? "OK"}
+            </Button>
+            <Button
+              variant="soft"
+              sx={{
+                margin: "var(--padding-md) 0",
+                width: "100%",
+                marginBottom: "0",
+              }}
+              onClick={() => closeTextPrompt(null)}
+              color="neutral"
+            >
+              Cancel
+            </Button>
+          </form>
+        </ModalDialog>
+      </Modal>
       {operationState && (
         <OperationView
           operationState={operationState}
@@ -0,0 +1,99 @@
+export type CrashReportEntry = {
+  name: string;
+  process: string;
+  timestamp: string;
+};
+
+export type CrashFrame = {
+  image: string;
+  imageIndex: number;
+  address: number;
+  imageOffset: number;
+  symbol: string | null;
+  symbolOffset: number | null;
+  // Resolved against a local build rather than by the device
+  symbolicated: boolean;
+};
+
+export type CrashReport = {
+  name: string;
+  process: string;
+  bundleId: string | null;
+  appVersion: string | null;
+  osVersion: string | null;
+  timestamp: string | null;
+  exception: {
+    exceptionType: string | null;
+    signal: string | null;
+    codes: string | null;
+    subtype: string | null;
+  } | null;
+  terminationReason: string | null;
+  threads: {
+    index: number;
+    name: string | null;
+    queue: string | null;
+    crashed: boolean;
+    frames: CrashFrame[];
+  }[];
+  images: {
+    name: string;
+    uuid: string | null;
+    base: number;
+    localBinary: string | null;
+  }[];
+};
+
+export const describeCrashReports = (
+  reports: CrashReportEntry[]
+): string[] => {
+  if (reports.length === 0) return ["No crash reports for this project"];
+  return reports.map(
+    (report) => `${report.timestamp} ${report.process} (${report.name})`
+  );
+};
+
+const describeFrame = (frame: CrashFrame, index: number): string => {
+  const location = frame.symbol
+    ? `${frame.symbol} + ${frame.symbolOffset ?? 0}`
+    : `0x${frame.address.toString(16)}`;
+  return `    ${index.toString().padEnd(3)} ${frame.image.padEnd(24)} ${location}${
+    frame.symbolicated ? "" : " (not symbolicated)"
+  }`;
+};
+
+// The crashed thread goes first, it's the one that matters most of the time
+export const describeCrashReport = (report: CrashReport): string[] => {
+  const exception = report.exception;
+  const threads = [...report.threads].sort(
+    (a, b) => Number(b.crashed) - Number(a.crashed)
+  );
+  return [
+    `${report.process}${report.appVersion ? ` ${report.appVersion}` : ""}${
+      report.bundleId ? ` (${report.bundleId})` : ""
+    }`,
+    `  Report: ${report.name}`,
+    `  Time: ${report.timestamp ?? "unknown"}`,
+    `  OS: ${report.osVersion ?? "unknown"}`,
+    ...(exception
+      ? [
+          `  Exception: ${[
+            exception.exceptionType,
+            exception.signal && `(${exception.signal})`,
+            exception.subtype,
+          ]
+            .filter(Boolean)
+            .join(" ")}`,
+        ]
+      : []),
+    ...(report.terminationReason
+      ? [`  Termination reason: ${report.terminationReason}`]
+      : []),
+    ...threads.flatMap((thread) => [
+      `  Thread ${thread.index}${thread.name ? ` "${thread.name}"` : ""}${
+        thread.queue ? ` (${thread.queue})` : ""
+      }${thread.crashed ? " crashed:" : ":"}`,
+      ...thread.frames.map(describeFrame),
+    ]),
+  ];
+};
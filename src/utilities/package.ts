@@ -0,0 +1,165 @@
+export type DependencyRequirement =
+  | { type: "range"; lowerBound: string; upperBound: string }
+  | { type: "exact"; version: string }
+  | { type: "branch"; name: string }
+  | { type: "revision"; revision: string };
+
+export type SwiftPackage = {
+  name: string;
+  toolsVersion: string | null;
+  platforms: { platformName: string; version: string }[];
+  products: {
+    name: string;
+    productType:
+      | "executable"
+      | "library"
+      | "plugin"
+      | "macro"
+      | "snippet"
+      | "test"
+      | "unknown";
+    libraryType: string | null;
+    targets: string[];
+  }[];
+  targets: {
+    name: string;
+    targetType:
+      | "regular"
+      | "executable"
+      | "test"
+      | "system"
+      | "binary"
+      | "plugin"
+      | "macro"
+      | "unknown";
+    path: string | null;
+    dependencies: {
+      kind: "target" | "product" | "byName";
+      name: string;
+      package: string | null;
+      platforms: string[];
+    }[];
+    resources: { rule: string; path: string; localization: string | null }[];
+    exclude: string[];
+    settings: {
+      tool: string;
+      kind: string;
+      values: string[];
+      platforms: string[];
+      configuration: string | null;
+    }[];
+  }[];
+  dependencies: {
+    identity: string;
+    kind: "sourceControl" | "fileSystem" | "registry";
+    location: string;
+    requirement: DependencyRequirement | null;
+  }[];
+  swiftLanguageModes: string[];
+};
+
+export const describeRequirement = (
+  requirement: DependencyRequirement | null
+): string => {
+  if (!requirement) return "local";
+  switch (requirement.type) {
+    case "range":
+      return `${requirement.lowerBound}..<${requirement.upperBound}`;
+    case "exact":
+      return requirement.version;
+    case "branch":
+      return `branch ${requirement.name}`;
+    case "revision":
+      return `revision ${requirement.revision}`;
+  }
+};
+
+// Targets with what they depend on, for the console
+export const describePackage = (pkg: SwiftPackage): string[] => {
+  const platforms = pkg.platforms.map(
+    (platform) => `${platform.platformName} ${platform.version}`
+  );
+  return [
+    `${pkg.name}${pkg.toolsVersion ? ` (tools ${pkg.toolsVersion})` : ""}`,
+    `  Platforms: ${platforms.length > 0 ? platforms.join(", ") : "none"}${
+      pkg.platforms.some((platform) => platform.platformName === "ios")
+        ? ""
+        : " (iOS is not declared)"
+    }`,
+    "  Products:",
+    ...pkg.products.map(
+      (product) =>
+        `    ${product.name} (${product.libraryType ?? product.productType}): ${product.targets.join(", ")}`
+    ),
+    "  Targets:",
+    ...pkg.targets.flatMap((target) => [
+      `    ${target.name} (${target.targetType})${
+        target.path ? ` at ${target.path}` : ""
+      }`,
+      ...target.dependencies.map(
+        (dependency) =>
+          `      -> ${dependency.name}${
+            dependency.package ? ` from ${dependency.package}` : ""
+          }${
+            dependency.platforms.length > 0
+              ? ` on ${dependency.platforms.join(", ")}`
+              : ""
+          }`
+      ),
+      ...target.resources.map(
+        (resource) => `      resource ${resource.path} (${resource.rule})`
+      ),
+    ]),
+    "  Dependencies:",
+    ...pkg.dependencies.map(
+      (dependency) =>
+        `    ${dependency.identity} ${describeRequirement(
+          dependency.requirement
+        )} (${dependency.location})`
+    ),
+  ];
+};
+
+export type ResolvedDependency = {
+  identity: string;
+  kind: "sourceControl" | "fileSystem" | "registry";
+  location: string;
+  version: string | null;
+  branch: string | null;
+  revision: string | null;
+};
+
+export type OutdatedDependency = {
+  identity: string;
+  location: string;
+  current: string;
+  latest: string | null;
+  latestAllowed: string | null;
+  error: string | null;
+};
+
+export const describeResolved = (pins: ResolvedDependency[]): string[] => {
+  if (pins.length === 0) return ["No resolved packages, run Resolve Packages"];
+  return pins.map(
+    (pin) =>
+      `${pin.identity} ${
+        pin.version ??
+        (pin.branch ? `branch ${pin.branch}` : null) ??
+        pin.revision?.slice(0, 7) ??
+        "local"
+      } (${pin.location})`
+  );
+};
+
+export const describeOutdated = (outdated: OutdatedDependency[]): string[] => {
+  if (outdated.length === 0) return ["All packages are up to date"];
+  return outdated.map((dependency) => {
+    if (dependency.error) return `${dependency.identity}: ${dependency.error}`;
+    const allowed =
+      dependency.latestAllowed &&
+      dependency.latestAllowed !== dependency.latest
+        ? `, ${dependency.latestAllowed} within the requirement`
+        : "";
+    return `${dependency.identity} ${dependency.current} -> ${dependency.latest}${allowed}`;
+  });
+};